@@ -14,15 +14,25 @@ pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
 /// other fields are included verbatim (including attributes). Attributes on the struct
 /// itself are also copied over to the generated structs.
 ///
+/// The `created_at` and `updated_at` timestamps are maintained by the database,
+/// so they are always skipped as well.
+///
+/// Fields of `UpdateX` are wrapped in `crate::patch::Patch`, which tells a missing
+/// field apart from `null` like a JSON Merge Patch. Only fields that are an `Option`
+/// in the model can be set to `null`, which clears them. Custom validators
+/// are only called with new values.
+///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,ignore
 /// #[model]
 /// struct User {
 ///   #[serde(skip_deserializing)]
 ///   id: Uuid,
 ///   name: String,
 ///   email: String,
+///   bio: Option<String>,
+///   updated_at: DateTime<Utc>,
 /// }
 ///
 /// // Generates:
@@ -30,11 +40,13 @@ pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
 /// struct CreateUser {
 ///   name: String,
 ///   email: String,
+///   bio: Option<String>,
 /// }
 ///
 /// struct UpdateUser {
-///   name: Option<String>,
-///   email: Option<String>,
+///   name: Patch<String>,
+///   email: Patch<String>,
+///   bio: Patch<String>,
 /// }
 /// ```
 #[proc_macro_attribute]
@@ -1,7 +1,7 @@
 use darling::{ast, FromDeriveInput, FromField};
 use proc_macro2::TokenTree;
 use quote::{format_ident, quote, ToTokens};
-use syn::Meta;
+use syn::{punctuated::Punctuated, Meta, Token};
 
 #[derive(Debug, FromDeriveInput)]
 #[darling(supports(struct_named), forward_attrs)]
@@ -15,6 +15,9 @@ struct ModelInputReceiver {
 	attrs: Vec<syn::Attribute>,
 }
 
+/// Fields that are maintained by the database, and never set by clients.
+const SERVER_MANAGED: [&str; 2] = ["created_at", "updated_at"];
+
 #[derive(Debug, FromField)]
 #[darling(forward_attrs)]
 struct ModelFieldReceiver {
@@ -50,6 +53,10 @@ pub fn from_input(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 			let attrs = &field.attrs;
 			let vis = &field.vis;
 
+			if SERVER_MANAGED.iter().any(|name| ident == name) {
+				return None;
+			}
+
 			// Skip fields with #[serde(skip_deserializing)] or #[serde(skip)]
 			if attrs.iter().any(|attr| {
 				let Meta::List(ref list) = attr.meta else {
@@ -78,12 +85,63 @@ pub fn from_input(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 		}
 	});
 
-	let update_fields = fields.iter().map(|(attrs, ident, ty, vis)| {
-		quote! {
-			#(#attrs)*
-			#vis #ident: Option<#ty>,
-		}
-	});
+	let mut validators = Vec::new();
+	let update_fields = fields
+		.iter()
+		.map(|(field_attrs, ident, ty, vis)| {
+			// Nullable fields can be cleared with `null`, others can only be left out
+			let (inner, nullable) = match option_inner(ty) {
+				Some(inner) => (inner, true),
+				None => (*ty, false),
+			};
+
+			let mut rules = Vec::new();
+			let mut attrs = field_attrs
+				.iter()
+				.filter_map(|attr| {
+					if !attr.path().is_ident("validate") {
+						return Some(Ok(attr.to_token_stream()));
+					}
+
+					match patch_rules(attr, update_ident.to_string(), ident, inner) {
+						Ok((mut list, wrappers)) => {
+							rules.append(&mut list);
+							validators.extend(wrappers);
+							None
+						}
+						Err(e) => Some(Err(e)),
+					}
+				})
+				.collect::<syn::Result<Vec<_>>>()?;
+
+			if !has_serde_default(field_attrs) {
+				attrs.push(quote!(#[serde(default)]));
+			}
+
+			if !nullable {
+				let schema = format!("crate::patch::Required<{}>", ty.to_token_stream());
+
+				attrs.push(
+					quote!(#[serde(deserialize_with = "crate::patch::deserialize_required")]),
+				);
+				attrs.push(quote!(#[schemars(with = #schema)]));
+			}
+
+			if !rules.is_empty() {
+				attrs.push(quote!(#[validate(#(#rules),*)]));
+			}
+
+			Ok(quote! {
+				#(#attrs)*
+				#vis #ident: crate::patch::Patch<#inner>,
+			})
+		})
+		.collect::<syn::Result<Vec<_>>>();
+
+	let update_fields = match update_fields {
+		Ok(fields) => fields,
+		Err(e) => return e.to_compile_error().into(),
+	};
 
 	quote! {
 		#input
@@ -101,6 +159,120 @@ pub fn from_input(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 				#update_fields
 			)*
 		}
+
+		#(#validators)*
 	}
 	.into()
 }
+
+/// Returns `T` if the type is `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+	let syn::Type::Path(path) = ty else {
+		return None;
+	};
+
+	let segment = path.path.segments.last()?;
+
+	if segment.ident != "Option" {
+		return None;
+	}
+
+	let syn::PathArguments::AngleBracketed(ref args) = segment.arguments else {
+		return None;
+	};
+
+	match args.args.first()? {
+		syn::GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+		_ => None,
+	}
+}
+
+/// Returns true if any of the attributes is a `#[serde(default)]`.
+fn has_serde_default(attrs: &[syn::Attribute]) -> bool {
+	attrs.iter().any(|attr| {
+		let Meta::List(ref list) = attr.meta else {
+			return false;
+		};
+
+		list.path.is_ident("serde")
+			&& list
+				.tokens
+				.to_token_stream()
+				.into_iter()
+				.any(|token| matches!(token, TokenTree::Ident(ref ident) if ident == "default"))
+	})
+}
+
+/// Rewrites the rules of a `#[validate(...)]` attribute for a `Patch` field.
+///
+/// Built-in rules work on `Patch` directly, but custom functions expect the
+/// inner type, so they are wrapped in a function that only validates new values.
+fn patch_rules(
+	attr: &syn::Attribute,
+	model: String,
+	field: &syn::Ident,
+	inner: &syn::Type,
+) -> syn::Result<(Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>)> {
+	let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+	let mut rules = Vec::new();
+	let mut wrappers = Vec::new();
+
+	for meta in metas {
+		let Meta::List(ref list) = meta else {
+			rules.push(meta.to_token_stream());
+			continue;
+		};
+
+		if !list.path.is_ident("custom") {
+			rules.push(meta.to_token_stream());
+			continue;
+		}
+
+		let mut args = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+
+		for arg in &mut args {
+			let Meta::NameValue(ref mut arg) = arg else {
+				continue;
+			};
+
+			if !arg.path.is_ident("function") {
+				continue;
+			}
+
+			let function = match &arg.value {
+				syn::Expr::Lit(syn::ExprLit {
+					lit: syn::Lit::Str(lit),
+					..
+				}) => lit.parse::<syn::Path>()?,
+				syn::Expr::Path(path) => path.path.clone(),
+				value => return Err(syn::Error::new_spanned(value, "expected a function path")),
+			};
+
+			let name = function
+				.segments
+				.last()
+				.map_or_else(String::new, |segment| segment.ident.to_string());
+			let wrapper = format_ident!("__{}_{}_{}", model, field, name);
+			let wrapper_str = wrapper.to_string();
+
+			wrappers.push(quote! {
+				#[doc(hidden)]
+				#[allow(non_snake_case)]
+				fn #wrapper(
+					value: &crate::patch::Patch<#inner>,
+				) -> ::std::result::Result<(), ::validator::ValidationError> {
+					match value.as_value() {
+						::std::option::Option::Some(value) => #function(value),
+						::std::option::Option::None => ::std::result::Result::Ok(()),
+					}
+				}
+			});
+
+			arg.value = syn::parse_quote!(#wrapper_str);
+		}
+
+		rules.push(quote!(custom(#args)));
+	}
+
+	Ok((rules, wrappers))
+}
@@ -0,0 +1,7 @@
+-- Titles are weighted higher than content when ranking search results.
+ALTER TABLE post ADD COLUMN search TSVECTOR NOT NULL GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', title), 'A') ||
+  setweight(to_tsvector('english', content), 'B')
+) STORED;
+
+CREATE INDEX post_search_idx ON post USING GIN (search);
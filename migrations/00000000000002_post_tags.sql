@@ -0,0 +1,24 @@
+CREATE TABLE tag (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  -- normalised to lowercase, see `route::tag::model::normalize`
+  name VARCHAR(32) NOT NULL UNIQUE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE post_tag (
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  tag_id UUID NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+  PRIMARY KEY (post_id, tag_id)
+);
+
+CREATE INDEX post_tag_tag_id_idx ON post_tag (tag_id);
+
+-- The names of the tags on a post, sorted by their bytes like `route::tag::model::normalize_all`.
+CREATE FUNCTION post_tags(post_id UUID) RETURNS TEXT[] AS $$
+  SELECT ARRAY(
+    SELECT tag.name FROM post_tag
+    JOIN tag ON tag.id = post_tag.tag_id
+    WHERE post_tag.post_id = $1
+    ORDER BY tag.name COLLATE "C"
+  )
+$$ LANGUAGE SQL STABLE;
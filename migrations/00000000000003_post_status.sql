@@ -0,0 +1,11 @@
+CREATE TYPE post_status AS ENUM ('draft', 'published', 'scheduled', 'archived');
+
+ALTER TABLE post
+  ADD COLUMN status post_status NOT NULL DEFAULT 'published',
+  ADD COLUMN published_at TIMESTAMPTZ,
+  -- scheduled posts are published by the scheduler once `published_at` has passed
+  ADD CONSTRAINT post_scheduled_published_at CHECK (status <> 'scheduled' OR published_at IS NOT NULL);
+
+UPDATE post SET published_at = created_at;
+
+CREATE INDEX post_scheduled_idx ON post (published_at) WHERE status = 'scheduled';
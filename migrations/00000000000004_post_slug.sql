@@ -0,0 +1,31 @@
+ALTER TABLE post ADD COLUMN slug VARCHAR(64);
+
+-- existing posts get a slug from their title, suffixed with part of
+-- their id when another post of the same author has the same title
+UPDATE post SET slug = numbered.slug
+FROM (
+  SELECT id, CASE
+    WHEN row_number() OVER (PARTITION BY user_id, base ORDER BY created_at, id) = 1 THEN base
+    ELSE base || '-' || left(id::text, 7)
+  END AS slug
+  FROM (
+    SELECT id, user_id, created_at,
+      COALESCE(NULLIF(trim(BOTH '-' FROM left(regexp_replace(lower(title), '[^a-z0-9]+', '-', 'g'), 56)), ''), 'post') AS base
+    FROM post
+  ) base
+) numbered
+WHERE post.id = numbered.id;
+
+ALTER TABLE post
+  ALTER COLUMN slug SET NOT NULL,
+  ADD CONSTRAINT post_user_id_slug_key UNIQUE (user_id, slug);
+
+-- previous slugs of posts whose title has changed, kept so that old links redirect
+CREATE TABLE post_slug (
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  slug VARCHAR(64) NOT NULL,
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  PRIMARY KEY (user_id, slug)
+);
+
+CREATE INDEX post_slug_post_id_idx ON post_slug (post_id);
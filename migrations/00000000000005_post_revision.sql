@@ -0,0 +1,17 @@
+-- the number of the current revision of the post
+ALTER TABLE post ADD COLUMN revision INT NOT NULL DEFAULT 1;
+
+-- Every version of the title and content of a post, numbered from 1.
+-- Rows are never updated, a restored revision is copied into a new one.
+CREATE TABLE post_revision (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  number INT NOT NULL,
+  title TEXT NOT NULL,
+  content TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (post_id, number)
+);
+
+INSERT INTO post_revision (post_id, number, title, content, created_at)
+SELECT id, 1, title, content, created_at FROM post;
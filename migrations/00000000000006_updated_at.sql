@@ -0,0 +1,32 @@
+-- Sets `updated_at` to the current time whenever a row is changed, ignoring the columns
+-- named in the arguments of the trigger. These are counters that do not count as edits,
+-- and generated columns, which are not computed yet in `NEW`.
+CREATE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+DECLARE
+  ignored TEXT[] := COALESCE(TG_ARGV, '{}');
+BEGIN
+  IF to_jsonb(NEW) - ignored IS DISTINCT FROM to_jsonb(OLD) - ignored THEN
+    NEW.updated_at = now();
+  END IF;
+
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE post ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE "user" ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE api_key ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+UPDATE post SET updated_at = created_at;
+UPDATE "user" SET updated_at = created_at;
+UPDATE api_key SET updated_at = created_at;
+
+CREATE TRIGGER post_updated_at BEFORE UPDATE ON post
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at('search');
+CREATE TRIGGER user_updated_at BEFORE UPDATE ON "user"
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER api_key_updated_at BEFORE UPDATE ON api_key
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- for `updated_since` filters
+CREATE INDEX post_updated_at_idx ON post (updated_at);
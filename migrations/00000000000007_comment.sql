@@ -0,0 +1,40 @@
+CREATE TABLE comment (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  -- replies are only one level deep, which is checked when they are created
+  parent_id UUID REFERENCES comment(id) ON DELETE CASCADE,
+  content TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TRIGGER comment_updated_at BEFORE UPDATE ON comment
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE INDEX comment_post_idx ON comment (post_id, created_at) WHERE parent_id IS NULL;
+CREATE INDEX comment_parent_idx ON comment (parent_id, created_at);
+
+ALTER TABLE post ADD COLUMN comment_count INT NOT NULL DEFAULT 0;
+
+-- Keeps `post.comment_count` in sync, including replies deleted along with their parent.
+CREATE FUNCTION count_comments() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    UPDATE post SET comment_count = comment_count + 1 WHERE id = NEW.post_id;
+  ELSE
+    UPDATE post SET comment_count = comment_count - 1 WHERE id = OLD.post_id;
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER comment_count AFTER INSERT OR DELETE ON comment
+  FOR EACH ROW EXECUTE FUNCTION count_comments();
+
+-- Comments on a post do not count as edits to it.
+DROP TRIGGER post_updated_at ON post;
+
+CREATE TRIGGER post_updated_at BEFORE UPDATE ON post
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at('search', 'comment_count');
@@ -0,0 +1,61 @@
+CREATE TYPE reaction_kind AS ENUM ('like', 'love', 'laugh', 'wow', 'sad');
+
+CREATE TABLE reaction (
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  kind reaction_kind NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (post_id, user_id, kind)
+);
+
+CREATE INDEX reaction_user_id_idx ON reaction (user_id, post_id);
+
+-- the number of reactions of each kind, omitting kinds without any
+ALTER TABLE post
+  ADD COLUMN reactions JSONB NOT NULL DEFAULT '{}',
+  ADD COLUMN like_count INT GENERATED ALWAYS AS (COALESCE((reactions->>'like')::int, 0)) STORED;
+
+-- for the "most liked" sort
+CREATE INDEX post_like_count_idx ON post (like_count);
+
+-- Keeps `post.reactions` in sync with the reaction table.
+CREATE FUNCTION count_reactions() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    UPDATE post
+    SET reactions = jsonb_set(
+      reactions, ARRAY[NEW.kind::text], to_jsonb(COALESCE((reactions->>NEW.kind::text)::int, 0) + 1)
+    )
+    WHERE id = NEW.post_id;
+  ELSE
+    UPDATE post
+    SET reactions = CASE
+      WHEN (reactions->>OLD.kind::text)::int > 1 THEN jsonb_set(
+        reactions, ARRAY[OLD.kind::text], to_jsonb((reactions->>OLD.kind::text)::int - 1)
+      )
+      ELSE reactions - OLD.kind::text
+    END
+    WHERE id = OLD.post_id;
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reaction_count AFTER INSERT OR DELETE ON reaction
+  FOR EACH ROW EXECUTE FUNCTION count_reactions();
+
+-- Reactions to a post do not count as edits to it.
+DROP TRIGGER post_updated_at ON post;
+
+CREATE TRIGGER post_updated_at BEFORE UPDATE ON post
+  FOR EACH ROW EXECUTE FUNCTION set_updated_at('search', 'comment_count', 'reactions', 'like_count');
+
+-- The kinds of reactions that a user left on a post, or none for anonymous viewers.
+CREATE FUNCTION post_reacted(post_id UUID, user_id UUID) RETURNS reaction_kind[] AS $$
+  SELECT ARRAY(
+    SELECT kind FROM reaction
+    WHERE reaction.post_id = $1 AND reaction.user_id = $2
+    ORDER BY kind
+  )
+$$ LANGUAGE SQL STABLE;
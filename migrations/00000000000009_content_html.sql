@@ -0,0 +1,9 @@
+-- The content of each revision rendered as sanitised HTML, filled in when it is
+-- first requested. Clear it when the renderer or its allowlist changes.
+ALTER TABLE post_revision ADD COLUMN content_html TEXT;
+
+-- Escapes text for HTML, such as post content before `ts_headline` wraps matches in tags.
+CREATE FUNCTION escape_html(text TEXT) RETURNS TEXT AS $$
+  SELECT replace(replace(replace(replace(replace(
+    text, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')
+$$ LANGUAGE sql IMMUTABLE;
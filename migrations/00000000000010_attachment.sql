@@ -0,0 +1,14 @@
+CREATE TABLE attachment (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  -- attachments outlive their post, so the file can still be deleted by its owner
+  post_id UUID REFERENCES post(id) ON DELETE SET NULL,
+  filename TEXT NOT NULL,
+  -- sniffed from the contents, not taken from the client
+  content_type TEXT NOT NULL,
+  size BIGINT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX attachment_user_idx ON attachment (user_id, created_at);
+CREATE INDEX attachment_post_idx ON attachment (post_id, created_at);
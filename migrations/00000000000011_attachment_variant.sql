@@ -0,0 +1,14 @@
+CREATE TYPE variant_status AS ENUM ('pending', 'ready', 'failed');
+
+-- images are resized into variants in the background, other files have no status
+ALTER TABLE attachment
+  ADD COLUMN variant_status variant_status,
+  -- the name, content type, dimensions and size of each variant
+  ADD COLUMN variants JSONB NOT NULL DEFAULT '[]',
+  -- images whose file could not be read or written are retried later, with a growing
+  -- delay, instead of blocking the rest of the queue
+  ADD COLUMN variant_attempts INT NOT NULL DEFAULT 0,
+  ADD COLUMN variant_retry_at TIMESTAMPTZ;
+
+-- the queue of images waiting to be processed
+CREATE INDEX attachment_pending_idx ON attachment (created_at) WHERE variant_status = 'pending';
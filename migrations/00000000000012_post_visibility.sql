@@ -0,0 +1,35 @@
+CREATE TYPE post_visibility AS ENUM ('public', 'unlisted', 'followers', 'private');
+
+ALTER TABLE post ADD COLUMN visibility post_visibility NOT NULL DEFAULT 'public';
+
+CREATE TABLE follow (
+  follower_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  followee_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (follower_id, followee_id),
+  CHECK (follower_id <> followee_id)
+);
+
+CREATE INDEX follow_followee_idx ON follow (followee_id, created_at);
+
+-- Whether a post can be read by a viewer (NULL for anonymous viewers) when it is requested
+-- directly. Authors can read all of their posts, and everyone else only published ones.
+CREATE FUNCTION post_readable(author UUID, status post_status, visibility post_visibility, viewer UUID)
+RETURNS BOOLEAN AS $$
+  SELECT author = viewer IS TRUE OR (status = 'published' AND CASE visibility
+    WHEN 'public' THEN TRUE
+    WHEN 'unlisted' THEN TRUE
+    WHEN 'followers' THEN EXISTS (
+      SELECT 1 FROM follow WHERE follower_id = viewer AND followee_id = author
+    )
+    ELSE FALSE
+  END)
+$$ LANGUAGE SQL STABLE;
+
+-- Whether a post is included in lists, searches and feeds for a viewer,
+-- which is the same as `post_readable` except for unlisted posts.
+CREATE FUNCTION post_listed(author UUID, status post_status, visibility post_visibility, viewer UUID)
+RETURNS BOOLEAN AS $$
+  SELECT author = viewer IS TRUE
+    OR (visibility <> 'unlisted' AND post_readable(author, status, visibility, viewer))
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,67 @@
+CREATE INDEX follow_follower_idx ON follow (follower_id, created_at);
+
+-- The home feed of each user, filled in when posts are published (fan-out on write) so reading
+-- a feed is a single index scan no matter how many users are followed. Visibility is checked
+-- when the feed is read, so entries are only removed when a post is no longer published.
+CREATE TABLE feed_entry (
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  author_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  published_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (user_id, post_id)
+);
+
+CREATE INDEX feed_entry_published_idx ON feed_entry (user_id, published_at, post_id);
+CREATE INDEX feed_entry_post_idx ON feed_entry (post_id);
+CREATE INDEX feed_entry_author_idx ON feed_entry (user_id, author_id);
+
+-- Adds a post to the feeds of the followers of its author when it is published,
+-- and removes it from them when it is unpublished.
+CREATE FUNCTION fan_out_post() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'UPDATE'
+    AND NEW.status IS NOT DISTINCT FROM OLD.status
+    AND NEW.published_at IS NOT DISTINCT FROM OLD.published_at
+  THEN
+    RETURN NULL;
+  END IF;
+
+  IF NEW.status = 'published' THEN
+    INSERT INTO feed_entry (user_id, post_id, author_id, published_at)
+    SELECT follower_id, NEW.id, NEW.user_id, NEW.published_at FROM follow
+    WHERE followee_id = NEW.user_id
+    ON CONFLICT (user_id, post_id) DO UPDATE SET published_at = EXCLUDED.published_at;
+  ELSIF TG_OP = 'UPDATE' THEN
+    DELETE FROM feed_entry WHERE post_id = NEW.id;
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_fan_out AFTER INSERT OR UPDATE OF status, published_at ON post
+  FOR EACH ROW EXECUTE FUNCTION fan_out_post();
+
+-- Fills in the published posts of a user when they are followed,
+-- and removes them again when they are unfollowed.
+CREATE FUNCTION fan_out_follow() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO feed_entry (user_id, post_id, author_id, published_at)
+    SELECT NEW.follower_id, id, user_id, published_at FROM post
+    WHERE user_id = NEW.followee_id AND status = 'published'
+    ON CONFLICT (user_id, post_id) DO NOTHING;
+  ELSE
+    DELETE FROM feed_entry WHERE user_id = OLD.follower_id AND author_id = OLD.followee_id;
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER follow_fan_out AFTER INSERT OR DELETE ON follow
+  FOR EACH ROW EXECUTE FUNCTION fan_out_follow();
+
+INSERT INTO feed_entry (user_id, post_id, author_id, published_at)
+SELECT follower_id, post.id, post.user_id, post.published_at FROM follow
+JOIN post ON post.user_id = follow.followee_id AND post.status = 'published';
@@ -0,0 +1,17 @@
+CREATE TABLE bookmark (
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (user_id, post_id)
+);
+
+CREATE INDEX bookmark_user_idx ON bookmark (user_id, created_at, post_id);
+CREATE INDEX bookmark_post_idx ON bookmark (post_id);
+
+-- Whether a user bookmarked a post, which is never the case for anonymous viewers.
+CREATE FUNCTION post_bookmarked(post_id UUID, user_id UUID) RETURNS BOOLEAN AS $$
+  SELECT EXISTS (
+    SELECT 1 FROM bookmark
+    WHERE bookmark.post_id = $1 AND bookmark.user_id = $2
+  )
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,58 @@
+ALTER TABLE "user" ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+
+-- Hidden by a moderator, or automatically after enough reports
+ALTER TABLE post ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TYPE report_reason AS ENUM (
+  'spam', 'harassment', 'hate', 'violence', 'sexual', 'misinformation', 'other'
+);
+CREATE TYPE report_status AS ENUM ('open', 'resolved', 'dismissed');
+
+CREATE TABLE report (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  post_id UUID NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+  reporter_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  reason report_reason NOT NULL,
+  details TEXT NOT NULL DEFAULT '',
+  status report_status NOT NULL DEFAULT 'open',
+  moderator_id UUID REFERENCES "user"(id) ON DELETE SET NULL,
+  resolved_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (post_id, reporter_id)
+);
+
+CREATE INDEX report_open_idx ON report (post_id) WHERE status = 'open';
+
+DROP FUNCTION post_listed;
+DROP FUNCTION post_readable;
+
+-- Whether a post can be read by a viewer (NULL for anonymous viewers) when it is requested
+-- directly. Authors can read all of their posts, and everyone else only published ones.
+-- Hidden posts can only be read by their author and admins.
+CREATE FUNCTION post_readable(
+  author UUID, status post_status, visibility post_visibility, hidden BOOLEAN, viewer UUID
+)
+RETURNS BOOLEAN AS $$
+  SELECT author = viewer IS TRUE OR (
+    status = 'published'
+    AND (NOT hidden OR EXISTS (SELECT 1 FROM "user" WHERE id = viewer AND is_admin))
+    AND CASE visibility
+      WHEN 'public' THEN TRUE
+      WHEN 'unlisted' THEN TRUE
+      WHEN 'followers' THEN EXISTS (
+        SELECT 1 FROM follow WHERE follower_id = viewer AND followee_id = author
+      )
+      ELSE FALSE
+    END
+  )
+$$ LANGUAGE SQL STABLE;
+
+-- Whether a post is included in lists, searches and feeds for a viewer, which is the
+-- same as `post_readable` except for unlisted posts, and hidden posts for admins.
+CREATE FUNCTION post_listed(
+  author UUID, status post_status, visibility post_visibility, hidden BOOLEAN, viewer UUID
+)
+RETURNS BOOLEAN AS $$
+  SELECT author = viewer IS TRUE
+    OR (NOT hidden AND visibility <> 'unlisted' AND post_readable(author, status, visibility, hidden, viewer))
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,23 @@
+-- A log of when each user created posts, for the rate limit. Unlike `post`, it keeps
+-- the posts that were deleted since, so deleting a post does not allow another one.
+CREATE TABLE post_creation (
+  user_id UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX post_creation_user_idx ON post_creation (user_id, created_at);
+
+-- Logs new posts, dropping the entries of the user that are too old to be counted.
+CREATE FUNCTION log_post_creation() RETURNS TRIGGER AS $$
+BEGIN
+  DELETE FROM post_creation
+  WHERE user_id = NEW.user_id AND created_at <= now() - interval '1 hour';
+
+  INSERT INTO post_creation (user_id, created_at) VALUES (NEW.user_id, NEW.created_at);
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_creation AFTER INSERT ON post
+  FOR EACH ROW EXECUTE FUNCTION log_post_creation();
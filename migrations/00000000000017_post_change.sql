@@ -0,0 +1,33 @@
+-- The last time that a post of each user was created, edited or deleted, which dates the
+-- feeds of posts. Unlike the newest `updated_at` of the listed posts, it never goes back
+-- when a post is unpublished or deleted. Users are not referenced, so their row outlives
+-- them and the global feed keeps its date when they are deleted along with their posts.
+CREATE TABLE post_change (
+  user_id UUID PRIMARY KEY,
+  changed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE FUNCTION record_post_change() RETURNS TRIGGER AS $$
+DECLARE
+  author UUID;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    author := OLD.user_id;
+  ELSE
+    author := NEW.user_id;
+  END IF;
+
+  INSERT INTO post_change (user_id, changed_at) VALUES (author, now())
+  ON CONFLICT (user_id) DO UPDATE
+  SET changed_at = GREATEST(post_change.changed_at, EXCLUDED.changed_at);
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_change AFTER INSERT OR DELETE ON post
+  FOR EACH ROW EXECUTE FUNCTION record_post_change();
+-- only edits, which change `updated_at`, and not counters such as `comment_count`
+CREATE TRIGGER post_change_update AFTER UPDATE ON post
+  FOR EACH ROW WHEN (OLD.updated_at IS DISTINCT FROM NEW.updated_at)
+  EXECUTE FUNCTION record_post_change();
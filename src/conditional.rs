@@ -0,0 +1,219 @@
+use axum::{
+	body::{self, Body},
+	extract::Request,
+	http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+
+use crate::extract::ETag;
+
+/// The headers of a `200 OK` response that are repeated in a `304 Not Modified`.
+const NOT_MODIFIED_HEADERS: [header::HeaderName; 5] = [
+	header::CACHE_CONTROL,
+	header::ETAG,
+	header::EXPIRES,
+	header::LAST_MODIFIED,
+	header::VARY,
+];
+
+/// Formats a timestamp as an HTTP date for the `Last-Modified` header.
+pub fn http_date(time: DateTime<Utc>) -> HeaderValue {
+	let date = time.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+	HeaderValue::from_str(&date).expect("HTTP dates are valid header values")
+}
+
+fn parse_http_date(value: &HeaderValue) -> Option<DateTime<Utc>> {
+	DateTime::parse_from_rfc2822(value.to_str().ok()?)
+		.ok()
+		.map(|time| time.with_timezone(&Utc))
+}
+
+/// The `If-None-Match` and `If-Modified-Since` headers of a request.
+#[derive(Debug, Default)]
+struct Preconditions {
+	if_none_match: Option<String>,
+	if_modified_since: Option<DateTime<Utc>>,
+}
+
+impl Preconditions {
+	fn from_headers(headers: &HeaderMap) -> Self {
+		let tags = headers
+			.get_all(header::IF_NONE_MATCH)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.collect::<Vec<_>>();
+
+		Self {
+			if_none_match: (!tags.is_empty()).then(|| tags.join(",")),
+			if_modified_since: headers
+				.get(header::IF_MODIFIED_SINCE)
+				.and_then(parse_http_date),
+		}
+	}
+
+	/// Returns whether the client already has the representation described by
+	/// the `ETag` and `Last-Modified` headers of a response (RFC 9110, section 13.2.2).
+	///
+	/// `If-Modified-Since` is only evaluated when `If-None-Match` is absent,
+	/// and entity tags are compared weakly, so `W/"a"` matches `"a"`.
+	fn is_fresh(&self, headers: &HeaderMap) -> bool {
+		if let Some(ref tags) = self.if_none_match {
+			let Some(etag) = headers
+				.get(header::ETAG)
+				.and_then(|etag| etag.to_str().ok())
+			else {
+				return false;
+			};
+			let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+			return tags
+				.split(',')
+				.any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag));
+		}
+
+		match (
+			self.if_modified_since,
+			headers.get(header::LAST_MODIFIED).and_then(parse_http_date),
+		) {
+			(Some(since), Some(modified)) => modified <= since,
+			_ => false,
+		}
+	}
+}
+
+/// Middleware that answers conditional `GET` and `HEAD` requests with
+/// `304 Not Modified` when the client's cached representation is still current.
+///
+/// JSON responses are tagged with a hash of their body, combined with the version
+/// tag that the route set, if any (see [`ETag::with_representation`]), so that any
+/// change to the body changes the tag. Routes can also set a `Last-Modified` header,
+/// but only if it changes along with every field of the body. This must run inside
+/// the compression layer, so that tags are computed from (and compared against)
+/// the uncompressed body, and `304` responses are left without a body.
+pub async fn not_modified(request: Request, next: Next) -> Response {
+	if !matches!(*request.method(), Method::GET | Method::HEAD) {
+		return next.run(request).await;
+	}
+
+	let preconditions = Preconditions::from_headers(request.headers());
+	let response = next.run(request).await;
+
+	if response.status() != StatusCode::OK {
+		return response;
+	}
+
+	let response = tag_json(response).await;
+
+	if !preconditions.is_fresh(response.headers()) {
+		return response;
+	}
+
+	let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+
+	for name in NOT_MODIFIED_HEADERS {
+		for value in response.headers().get_all(&name) {
+			not_modified.headers_mut().append(&name, value.clone());
+		}
+	}
+
+	not_modified
+}
+
+/// Sets the `ETag` header of JSON responses to a hash of their body, keeping the
+/// version of the tag that the route set, if any.
+async fn tag_json(response: Response) -> Response {
+	let is_json = response
+		.headers()
+		.get(header::CONTENT_TYPE)
+		.is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+
+	if !is_json {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+	let Ok(bytes) = body::to_bytes(body, usize::MAX).await else {
+		return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+	};
+
+	let etag = match parts.headers.get(header::ETAG).map(HeaderValue::to_str) {
+		Some(Ok(tag)) => ETag::with_representation(tag, &bytes),
+		_ => ETag::hash(&bytes),
+	};
+
+	if let Ok(etag) = HeaderValue::from_str(etag.as_str()) {
+		parts.headers.insert(header::ETAG, etag);
+	}
+
+	Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod test {
+	use axum::http::{header, HeaderMap, HeaderValue};
+	use chrono::{TimeZone, Utc};
+
+	use super::{http_date, Preconditions};
+
+	fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+		pairs
+			.iter()
+			.map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+			.collect()
+	}
+
+	#[test]
+	fn test_if_none_match() {
+		let response = headers(&[(header::ETAG, "\"abc\"")]);
+		let fresh = |value| {
+			Preconditions::from_headers(&headers(&[(header::IF_NONE_MATCH, value)]))
+				.is_fresh(&response)
+		};
+
+		assert!(fresh("\"abc\""));
+		assert!(fresh("W/\"abc\""));
+		assert!(fresh("\"xyz\", \"abc\""));
+		assert!(fresh("*"));
+		assert!(!fresh("\"xyz\""));
+		assert!(!Preconditions::default().is_fresh(&response));
+	}
+
+	#[test]
+	fn test_if_modified_since() {
+		let modified = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+		let mut response = headers(&[(header::ETAG, "\"abc\"")]);
+
+		response.insert(header::LAST_MODIFIED, http_date(modified));
+
+		assert_eq!(
+			response[header::LAST_MODIFIED],
+			"Tue, 02 Jan 2024 03:04:05 GMT"
+		);
+
+		let fresh = |pairs: &[(header::HeaderName, &'static str)]| {
+			Preconditions::from_headers(&headers(pairs)).is_fresh(&response)
+		};
+
+		assert!(fresh(&[(
+			header::IF_MODIFIED_SINCE,
+			"Tue, 02 Jan 2024 03:04:05 GMT"
+		)]));
+		assert!(fresh(&[(
+			header::IF_MODIFIED_SINCE,
+			"Wed, 03 Jan 2024 00:00:00 GMT"
+		)]));
+		assert!(!fresh(&[(
+			header::IF_MODIFIED_SINCE,
+			"Mon, 01 Jan 2024 00:00:00 GMT"
+		)]));
+		assert!(!fresh(&[(header::IF_MODIFIED_SINCE, "not a date")]));
+		// If-None-Match takes precedence over If-Modified-Since
+		assert!(!fresh(&[
+			(header::IF_NONE_MATCH, "\"xyz\""),
+			(header::IF_MODIFIED_SINCE, "Wed, 03 Jan 2024 00:00:00 GMT"),
+		]));
+	}
+}
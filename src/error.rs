@@ -16,6 +16,8 @@ use serde::Serialize;
 use tower_governor::GovernorError;
 use validator::{ValidationErrors, ValidationErrorsKind};
 
+use crate::extract::ETag;
+
 pub use std::collections::HashMap as Map;
 
 pub trait ErrorShape: Sized {
@@ -65,11 +67,31 @@ pub enum AppError {
 	Database(#[from] sqlx::Error),
 	#[error("governor error: {0}")]
 	Governor(#[from] tower_governor::GovernorError),
+	#[error("precondition failed, current entity tag is {0:?}")]
+	PreconditionFailed(ETag),
+	#[error("unsupported media type")]
+	UnsupportedMediaType,
+	#[error("json patch error: {0}")]
+	JsonPatch(String),
+	#[error("multipart error: {0}")]
+	Multipart(String),
+	#[error("payload too large")]
+	PayloadTooLarge,
+	#[error("storage error: {0}")]
+	Storage(#[from] crate::storage::Error),
 }
 
 impl From<axum_jsonschema::JsonSchemaRejection> for AppError {
 	fn from(error: axum_jsonschema::JsonSchemaRejection) -> Self {
-		Self::Json(error)
+		match error {
+			// Bodies over the limit of the route get the same error as oversized uploads
+			JsonSchemaRejection::Json(ref rejection)
+				if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE =>
+			{
+				Self::PayloadTooLarge
+			}
+			error => Self::Json(error),
+		}
 	}
 }
 
@@ -79,6 +101,12 @@ impl<E> From<sqlx::Error> for RouteError<E> {
 	}
 }
 
+impl<E> From<crate::storage::Error> for RouteError<E> {
+	fn from(error: crate::storage::Error) -> Self {
+		Self::App(error.into())
+	}
+}
+
 impl IntoResponse for AppError {
 	fn into_response(self) -> Response<Body> {
 		ErrorShape::into_response(self)
@@ -212,9 +240,13 @@ impl ErrorShape for AppError {
 		match self {
 			Self::Validation(errors) => errors.status(),
 			Self::Json(error) => error.status(),
-			Self::Query(..) | Self::Path(..) => StatusCode::BAD_REQUEST,
-			Self::Database(..) => StatusCode::INTERNAL_SERVER_ERROR,
+			Self::Query(..) | Self::Path(..) | Self::Multipart(..) => StatusCode::BAD_REQUEST,
+			Self::Database(..) | Self::Storage(..) => StatusCode::INTERNAL_SERVER_ERROR,
 			Self::Governor(error) => error.status(),
+			Self::PreconditionFailed(..) => StatusCode::PRECONDITION_FAILED,
+			Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			Self::JsonPatch(..) => StatusCode::UNPROCESSABLE_ENTITY,
+			Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
 		}
 	}
 
@@ -234,8 +266,20 @@ impl ErrorShape for AppError {
 			Self::Json(error) => error.into_errors(),
 			Self::Query(error) => Message::new(error.to_string()).into_vec(),
 			Self::Governor(error) => error.into_errors(),
-			Self::Database(..) => Message::new("internal_error").into_vec(),
+			Self::Database(..) | Self::Storage(..) => Message::new("internal_error").into_vec(),
 			Self::Path(error) => error.into_errors(),
+			Self::PreconditionFailed(current) => Message::new("precondition_failed")
+				.content("The resource has changed since you last fetched it.")
+				.detail("etag", current.as_str())
+				.into_vec(),
+			Self::UnsupportedMediaType => Message::new("unsupported_media_type")
+				.content("Expected application/merge-patch+json or application/json-patch+json.")
+				.into_vec(),
+			Self::JsonPatch(error) => Message::new("invalid_json_patch").content(error).into_vec(),
+			Self::Multipart(error) => Message::new("invalid_multipart").content(error).into_vec(),
+			Self::PayloadTooLarge => Message::new("payload_too_large")
+				.content("The request body is too large.")
+				.into_vec(),
 		}
 	}
 }
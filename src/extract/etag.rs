@@ -0,0 +1,253 @@
+use std::convert::Infallible;
+
+use aide::{
+	openapi::{HeaderStyle, Parameter, ParameterData, ParameterSchemaOrContent, SchemaObject},
+	OperationInput, OperationOutput,
+};
+use axum::{
+	body::Body,
+	extract::FromRequestParts,
+	http::{header, request, HeaderValue, Response},
+	response::IntoResponse,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+	conditional::http_date,
+	error::{AppError, RouteError},
+};
+
+use super::response_header;
+
+/// A strong entity tag (RFC 9110) identifying a version of a resource.
+///
+/// It is sent in the `ETag` header of a response, and clients send it back
+/// in `If-Match` to only update the resource if it has not changed since.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+	/// Creates an entity tag from a hash of the JSON representation of `value`,
+	/// which must contain every field that a client can change.
+	pub fn of(value: &impl Serialize) -> Self {
+		Self::hash(&serde_json::to_vec(value).unwrap_or_default())
+	}
+
+	/// Creates an entity tag from a hash of `bytes`.
+	pub fn hash(bytes: &[u8]) -> Self {
+		let hash = Sha256::digest(bytes);
+
+		Self(format!("\"{}\"", URL_SAFE_NO_PAD.encode(&hash[..16])))
+	}
+
+	/// Combines a version tag, such as one from [`ETag::of`], with a hash of the
+	/// representation that it is sent with, as `"<version>.<hash>"`. The tag then changes
+	/// with every field of the representation, while [`IfMatch`] only compares the version.
+	pub fn with_representation(tag: &str, bytes: &[u8]) -> Self {
+		let hash = Self::hash(bytes);
+
+		Self(format!("\"{}.{}\"", version(tag), version(&hash.0)))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+/// Returns the version that an entity tag identifies, leaving out the quotes
+/// and the hash of the representation added by [`ETag::with_representation`].
+fn version(tag: &str) -> &str {
+	let tag = tag.trim_matches('"');
+
+	tag.split_once('.').map_or(tag, |(version, _)| version)
+}
+
+/// Extractor for the `If-Match` header, which makes an update conditional
+/// on the resource not having changed since the client last fetched it.
+///
+/// The header is optional: without it, or with [`IfMatch::default`], updates are unconditional.
+///
+/// ```rust
+/// async fn route(if_match: IfMatch) -> Result<(), RouteError> {
+///   let post = fetch_post().await?;
+///
+///   if_match.check(&ETag::of(&post))?;
+///   // ...
+/// }
+/// ```
+#[derive(Default)]
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+	/// Returns [`AppError::PreconditionFailed`] unless the header is missing,
+	/// is `*`, or lists a tag with the same version as the `current` entity tag.
+	#[allow(clippy::result_large_err)]
+	pub fn check<E>(&self, current: &ETag) -> Result<(), RouteError<E>> {
+		let Some(ref tags) = self.0 else {
+			return Ok(());
+		};
+
+		if tags
+			.split(',')
+			.map(str::trim)
+			.any(|tag| tag == "*" || version(tag) == version(current.as_str()))
+		{
+			return Ok(());
+		}
+
+		Err(RouteError::App(AppError::PreconditionFailed(
+			current.clone(),
+		)))
+	}
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+	S: Send + Sync,
+{
+	type Rejection = Infallible;
+
+	async fn from_request_parts(
+		parts: &mut request::Parts,
+		_state: &S,
+	) -> Result<Self, Self::Rejection> {
+		let tags = parts
+			.headers
+			.get_all(header::IF_MATCH)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.collect::<Vec<_>>();
+
+		Ok(Self((!tags.is_empty()).then(|| tags.join(","))))
+	}
+}
+
+impl OperationInput for IfMatch {
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		let schema = ctx.schema.subschema_for::<String>().into_object();
+
+		operation
+			.parameters
+			.push(aide::openapi::ReferenceOr::Item(Parameter::Header {
+				parameter_data: ParameterData {
+					name: header::IF_MATCH.to_string(),
+					description: Some(
+						"Only apply the change if the resource still has one of these \
+						entity tags, taken from the `ETag` header of a previous response. \
+						Otherwise, `412 Precondition Failed` is returned."
+							.into(),
+					),
+					required: false,
+					deprecated: None,
+					format: ParameterSchemaOrContent::Schema(SchemaObject {
+						json_schema: schema.into(),
+						example: None,
+						external_docs: None,
+					}),
+					example: None,
+					examples: Default::default(),
+					explode: None,
+					extensions: Default::default(),
+				},
+				style: HeaderStyle::Simple,
+			}));
+	}
+}
+
+/// A response with an `ETag` header identifying the version of the resource,
+/// and optionally a `Last-Modified` header with the time it last changed.
+pub struct Tagged<T> {
+	etag: ETag,
+	last_modified: Option<DateTime<Utc>>,
+	vary: Option<&'static str>,
+	body: T,
+}
+
+impl<T> Tagged<T> {
+	pub fn new(etag: ETag, body: T) -> Self {
+		Self {
+			etag,
+			last_modified: None,
+			vary: None,
+			body,
+		}
+	}
+
+	#[must_use]
+	pub fn last_modified(mut self, time: DateTime<Utc>) -> Self {
+		self.last_modified = Some(time);
+		self
+	}
+
+	/// Adds a `Vary` header with the request headers that the body depends on.
+	#[must_use]
+	pub fn vary(mut self, headers: &'static str) -> Self {
+		self.vary = Some(headers);
+		self
+	}
+}
+
+impl<T> IntoResponse for Tagged<T>
+where
+	T: IntoResponse,
+{
+	fn into_response(self) -> Response<Body> {
+		let mut response = self.body.into_response();
+		let headers = response.headers_mut();
+
+		if let Ok(etag) = HeaderValue::from_str(self.etag.as_str()) {
+			headers.insert(header::ETAG, etag);
+		}
+
+		if let Some(time) = self.last_modified {
+			headers.insert(header::LAST_MODIFIED, http_date(time));
+		}
+
+		if let Some(vary) = self.vary {
+			headers.insert(header::VARY, HeaderValue::from_static(vary));
+		}
+
+		response
+	}
+}
+
+impl<T> OperationOutput for Tagged<T>
+where
+	T: OperationOutput,
+{
+	type Inner = T::Inner;
+
+	fn operation_response(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Option<aide::openapi::Response> {
+		let mut response = T::operation_response(ctx, operation)?;
+
+		response.headers.insert(
+			header::ETAG.to_string(),
+			response_header(
+				ctx,
+				"The entity tag of the returned version of the resource, for use in `If-Match`.",
+			),
+		);
+		response.headers.insert(
+			header::LAST_MODIFIED.to_string(),
+			response_header(ctx, "The last time the resource was changed, if known."),
+		);
+
+		Some(response)
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|response| vec![(Some(200), response)])
+			.unwrap_or_default()
+	}
+}
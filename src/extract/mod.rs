@@ -1,8 +1,19 @@
+mod etag;
+mod pagination;
+mod patch;
 mod session;
+mod upload;
 
-pub use session::{Session, SessionOrApiKey};
+pub use etag::{ETag, IfMatch, Tagged};
+pub use pagination::{Page, Pagination};
+pub use patch::PatchRequest;
+pub use session::{AdminSession, MaybeSession, Session, SessionOrApiKey};
+pub use upload::Upload;
 
-use aide::OperationIo;
+use aide::{
+	openapi::{Header, HeaderStyle, ParameterSchemaOrContent, ReferenceOr, SchemaObject},
+	OperationIo,
+};
 use axum::{
 	body::Body,
 	extract::{FromRequest, FromRequestParts, Request},
@@ -126,3 +137,23 @@ where
 		Ok(Self(result))
 	}
 }
+
+/// Documents a string header of a response in the `OpenAPI` specification.
+fn response_header(ctx: &mut aide::gen::GenContext, description: &str) -> ReferenceOr<Header> {
+	let schema = ctx.schema.subschema_for::<String>().into_object();
+
+	ReferenceOr::Item(Header {
+		description: Some(description.into()),
+		style: HeaderStyle::Simple,
+		required: false,
+		deprecated: None,
+		format: ParameterSchemaOrContent::Schema(SchemaObject {
+			json_schema: schema.into(),
+			example: None,
+			external_docs: None,
+		}),
+		example: None,
+		examples: Default::default(),
+		extensions: Default::default(),
+	})
+}
@@ -0,0 +1,231 @@
+use aide::{OperationInput, OperationOutput};
+use axum::{
+	body::Body,
+	extract::{FromRequestParts, OriginalUri},
+	http::{header, request, HeaderValue, Response, Uri},
+	response::IntoResponse,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, Postgres, QueryBuilder};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{
+	error::{AppError, RouteError},
+	route::model::{Cursor, Keyset, Paginate, Sort},
+	Database,
+};
+
+use super::{response_header, Json, Query};
+
+/// Extractor that deserializes and validates [`Paginate`] from the query string,
+/// keeping the request URI so the response can link to the neighbouring pages.
+///
+/// ```rust
+/// async fn route(pagination: Pagination) -> Result<Page<Post>, RouteError> {
+///   let posts = pagination
+///     .fetch(&database, "SELECT *", Sort::NEWEST, |query| {
+///       query.push(" FROM post WHERE TRUE");
+///     })
+///     .await?;
+///
+///   Ok(posts)
+/// }
+/// ```
+pub struct Pagination {
+	pub paginate: Paginate,
+	uri: Uri,
+}
+
+impl Pagination {
+	/// Fetches a single page of rows, along with the total number of rows
+	/// unless the client opted out of counting them.
+	///
+	/// `from` must push the `FROM` and `WHERE` clauses of the query, and is called
+	/// once for the page itself (after `select`) and once for the `COUNT(*)`.
+	///
+	/// A cursor created for a different [`Sort`] is rejected with a validation error.
+	pub async fn fetch<T, E, F>(
+		self,
+		database: &Database,
+		select: &str,
+		sort: Sort,
+		from: F,
+	) -> Result<Page<T>, RouteError<E>>
+	where
+		T: for<'r> FromRow<'r, PgRow> + Keyset + Send + Unpin,
+		F: for<'q> Fn(&mut QueryBuilder<'q, Postgres>),
+	{
+		if let Some(ref cursor) = self.paginate.cursor {
+			if cursor.column != sort.column || cursor.descending != sort.descending {
+				let mut errors = ValidationErrors::new();
+
+				errors.add("cursor", ValidationError::new("cursor_sort_mismatch"));
+
+				return Err(RouteError::App(AppError::Validation(errors)));
+			}
+		}
+
+		let mut query = QueryBuilder::new(select);
+
+		from(&mut query);
+		self.paginate.push_bounds(&mut query, sort);
+
+		let items = query.build_query_as().fetch_all(database);
+
+		let (mut items, total) = if self.paginate.count {
+			let mut count = QueryBuilder::new("SELECT COUNT(*)");
+
+			from(&mut count);
+
+			let total = count.build_query_scalar().fetch_one(database);
+			let (items, total) = tokio::try_join!(items, total)?;
+
+			(items, Some(total))
+		} else {
+			(items.await?, None)
+		};
+
+		let (next_cursor, prev_cursor) = self.paginate.cursors(&mut items, sort);
+
+		Ok(Page {
+			items,
+			page: self.paginate.cursor.is_none().then_some(self.paginate.page),
+			size: self.paginate.size,
+			total,
+			has_more: next_cursor.is_some(),
+			next_cursor,
+			prev_cursor,
+			uri: self.uri,
+		})
+	}
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Pagination
+where
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(
+		parts: &mut request::Parts,
+		state: &S,
+	) -> Result<Self, Self::Rejection> {
+		let Query(paginate) = Query::<Paginate>::from_request_parts(parts, state).await?;
+		let uri = parts
+			.extensions
+			.get::<OriginalUri>()
+			.map_or_else(|| parts.uri.clone(), |uri| uri.0.clone());
+
+		Ok(Self { paginate, uri })
+	}
+}
+
+impl OperationInput for Pagination {
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		Query::<Paginate>::operation_input(ctx, operation);
+	}
+}
+
+/// A single page of items from a paginated list.
+///
+/// Cursors to the next and previous pages are also sent in a `Link` header
+/// (RFC 8288), with `rel="next"` and `rel="prev"` respectively.
+#[derive(Serialize, JsonSchema)]
+#[allow(clippy::struct_field_names)]
+pub struct Page<T> {
+	/// The items on this page, in the requested order.
+	pub items: Vec<T>,
+	/// The page number (1-indexed), or `null` if the page was requested with a cursor.
+	pub page: Option<i64>,
+	/// The maximum number of items on a page.
+	pub size: i64,
+	/// The total number of items across all pages. Omitted when
+	/// the page was requested with `count=false`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub total: Option<i64>,
+	/// Whether there are more items after this page.
+	pub has_more: bool,
+	/// A cursor to the page after this one, if there is one.
+	pub next_cursor: Option<Cursor>,
+	/// A cursor to the page before this one, if there is one.
+	pub prev_cursor: Option<Cursor>,
+	#[serde(skip)]
+	uri: Uri,
+}
+
+impl<T> Page<T> {
+	/// Builds a link to the page at `cursor`, keeping every other
+	/// query parameter (such as filters) of the original request.
+	fn link(&self, cursor: &Cursor, rel: &str) -> String {
+		let mut params = self
+			.uri
+			.query()
+			.and_then(|query| serde_urlencoded::from_str::<Vec<(String, String)>>(query).ok())
+			.unwrap_or_default();
+
+		params.retain(|(key, _)| key != "cursor" && key != "page");
+		params.push(("cursor".into(), cursor.encode()));
+
+		let query = serde_urlencoded::to_string(params).unwrap_or_default();
+
+		format!("<{}?{query}>; rel=\"{rel}\"", self.uri.path())
+	}
+}
+
+impl<T> IntoResponse for Page<T>
+where
+	T: Serialize,
+{
+	fn into_response(self) -> Response<Body> {
+		let links = [(&self.next_cursor, "next"), (&self.prev_cursor, "prev")]
+			.into_iter()
+			.filter_map(|(cursor, rel)| cursor.as_ref().map(|cursor| self.link(cursor, rel)))
+			.collect::<Vec<_>>()
+			.join(", ");
+
+		let mut response = Json(self).into_response();
+
+		if let Ok(links) = HeaderValue::from_str(&links) {
+			if !links.is_empty() {
+				response.headers_mut().insert(header::LINK, links);
+			}
+		}
+
+		response
+	}
+}
+
+impl<T> OperationOutput for Page<T>
+where
+	T: JsonSchema,
+{
+	type Inner = Self;
+
+	fn operation_response(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Option<aide::openapi::Response> {
+		let mut response = Json::<Self>::operation_response(ctx, operation)?;
+
+		response.headers.insert(
+			header::LINK.to_string(),
+			response_header(
+				ctx,
+				"Links to the next and previous pages, with `rel=\"next\"` and `rel=\"prev\"`.",
+			),
+		);
+
+		Some(response)
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|response| vec![(Some(200), response)])
+			.unwrap_or_default()
+	}
+}
@@ -0,0 +1,207 @@
+use aide::{
+	openapi::{MediaType, ReferenceOr, SchemaObject},
+	OperationInput,
+};
+use axum::{
+	extract::{FromRequest, Request},
+	http::header,
+};
+use axum_jsonschema::JsonSchemaRejection;
+use schemars::{schema::Schema, JsonSchema};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use validator::Validate;
+
+use crate::error::AppError;
+
+use super::Json;
+
+/// The media type of a JSON Merge Patch (RFC 7396).
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+/// The media type of a JSON Patch (RFC 6902).
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+/// Extractor for the body of a `PATCH` request, chosen by its `Content-Type`.
+///
+/// A JSON Merge Patch (or plain JSON) is deserialized into the update `T` directly,
+/// while a JSON Patch is a list of operations that must be applied to the current
+/// resource with [`PatchRequest::resolve`] before it becomes an update.
+///
+/// ```rust
+/// async fn route(patch: PatchRequest<UpdatePost>) -> Result<(), RouteError> {
+///   let post = fetch_post().await?;
+///   let input = patch.resolve(&post)?;
+///   // ...
+/// }
+/// ```
+pub enum PatchRequest<T> {
+	Merge(T),
+	Json(json_patch::Patch),
+}
+
+impl<T> PatchRequest<T>
+where
+	T: DeserializeOwned + Validate,
+{
+	/// Turns the request into an update of the `current` resource.
+	///
+	/// A JSON Patch is applied to the JSON representation of `current`, and the
+	/// difference is then read as a merge patch, so removed fields become `null`.
+	#[allow(clippy::result_large_err)]
+	pub fn resolve(self, current: &impl Serialize) -> Result<T, AppError> {
+		let patch = match self {
+			Self::Merge(update) => return Ok(update),
+			Self::Json(patch) => patch,
+		};
+
+		let before = serde_json::to_value(current).unwrap_or_default();
+		let mut after = before.clone();
+
+		json_patch::patch(&mut after, &patch)
+			.map_err(|error| AppError::JsonPatch(error.to_string()))?;
+
+		let update = serde_json::from_value::<T>(merge_diff(&before, after))
+			.map_err(|error| AppError::JsonPatch(error.to_string()))?;
+
+		update.validate()?;
+
+		Ok(update)
+	}
+}
+
+/// Returns the merge patch that turns `before` into `after`.
+fn merge_diff(before: &Value, after: Value) -> Value {
+	match (before, after) {
+		(Value::Object(before), Value::Object(mut after)) => {
+			let mut diff = Map::new();
+
+			for (key, value) in before {
+				match after.remove(key) {
+					None => {
+						diff.insert(key.clone(), Value::Null);
+					}
+					Some(changed) if changed != *value => {
+						diff.insert(key.clone(), merge_diff(value, changed));
+					}
+					Some(_) => {}
+				}
+			}
+
+			diff.extend(after);
+
+			Value::Object(diff)
+		}
+		(_, after) => after,
+	}
+}
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for PatchRequest<T>
+where
+	T: DeserializeOwned + Validate + JsonSchema + 'static,
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+		let media_type = req
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.split(';').next())
+			.map(|value| value.trim().to_ascii_lowercase())
+			.unwrap_or_default();
+
+		match media_type.as_str() {
+			"application/json" | MERGE_PATCH => {
+				let Json(update) = Json::<T>::from_request(req, state).await?;
+
+				Ok(Self::Merge(update))
+			}
+			JSON_PATCH => {
+				let axum::Json(patch) = axum::Json::from_request(req, state)
+					.await
+					.map_err(JsonSchemaRejection::Json)?;
+
+				Ok(Self::Json(patch))
+			}
+			_ => Err(AppError::UnsupportedMediaType),
+		}
+	}
+}
+
+impl<T> OperationInput for PatchRequest<T>
+where
+	T: JsonSchema,
+{
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		axum::Json::<T>::operation_input(ctx, operation);
+
+		let Some(ReferenceOr::Item(ref mut body)) = operation.request_body else {
+			return;
+		};
+
+		if let Some(json) = body.content.get("application/json").cloned() {
+			body.content.insert(MERGE_PATCH.into(), json);
+		}
+
+		body.content.insert(
+			JSON_PATCH.into(),
+			MediaType {
+				schema: Some(SchemaObject {
+					json_schema: serde_json::from_value(json_patch_schema())
+						.unwrap_or(Schema::Bool(true)),
+					example: None,
+					external_docs: None,
+				}),
+				..Default::default()
+			},
+		);
+	}
+}
+
+/// The JSON schema of a JSON Patch document.
+fn json_patch_schema() -> Value {
+	json!({
+		"type": "array",
+		"description": "A list of operations to apply to the resource in order (RFC 6902).",
+		"items": {
+			"type": "object",
+			"required": ["op", "path"],
+			"properties": {
+				"op": {
+					"type": "string",
+					"enum": ["add", "remove", "replace", "move", "copy", "test"],
+				},
+				"path": {
+					"type": "string",
+					"description": "A JSON Pointer to the target location, such as `/title`.",
+				},
+				"from": {
+					"type": "string",
+					"description": "A JSON Pointer to the source location of `move` and `copy`.",
+				},
+				"value": {
+					"description": "The value to add, replace or test.",
+				},
+			},
+		},
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_merge_diff() {
+		let before =
+			json!({ "title": "Title", "tags": ["a"], "published_at": "2024-01-01T00:00:00Z" });
+		let after = json!({ "title": "Title", "tags": ["a", "b"] });
+
+		assert_eq!(
+			merge_diff(&before, after),
+			json!({ "tags": ["a", "b"], "published_at": null })
+		);
+	}
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use aide::OperationInput;
+use aide::{openapi::SecurityRequirement, OperationInput};
 use axum::{
 	extract::{FromRef, FromRequestParts},
 	http::{header, request},
@@ -145,3 +145,94 @@ impl OperationInput for Session {
 		]);
 	}
 }
+
+/// Extracts the session like [`Session`], and only allows admins.
+///
+/// If the user is not an admin, a [`auth::Error::AdminRequired`] is returned.
+///
+/// ```rust
+/// async fn route(AdminSession(session): AdminSession) {
+///   println!("{} is an admin", session.user.username);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AdminSession(pub Session);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for AdminSession
+where
+	Database: FromRef<S>,
+	S: Sync + Send,
+{
+	type Rejection = RouteError<auth::Error>;
+
+	async fn from_request_parts(
+		parts: &mut request::Parts,
+		state: &S,
+	) -> Result<Self, Self::Rejection> {
+		let session = Session::from_request_parts(parts, state).await?;
+
+		if !session.user.is_admin {
+			return Err(auth::Error::AdminRequired.into());
+		}
+
+		Ok(Self(session))
+	}
+}
+
+impl OperationInput for AdminSession {
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		Session::operation_input(ctx, operation);
+	}
+}
+
+/// Extracts the session like [`Session`], if the request has one.
+///
+/// Unlike `Option<Session>`, this only returns `None` when no session cookie or
+/// API key was sent. Invalid credentials are still rejected.
+///
+/// ```rust
+/// async fn route(MaybeSession(session): MaybeSession) {
+///   println!("{:?}", session.map(|session| session.user));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MaybeSession(pub Option<Session>);
+
+impl MaybeSession {
+	/// Returns the id of the authenticated user, if there is one.
+	pub fn user_id(&self) -> Option<Uuid> {
+		self.0.as_ref().map(|session| session.user.id)
+	}
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for MaybeSession
+where
+	Database: FromRef<S>,
+	S: Sync + Send,
+{
+	type Rejection = RouteError<auth::Error>;
+
+	async fn from_request_parts(
+		parts: &mut request::Parts,
+		state: &S,
+	) -> Result<Self, Self::Rejection> {
+		match Session::from_request_parts(parts, state).await {
+			Ok(session) => Ok(Self(Some(session))),
+			Err(RouteError::Route(auth::Error::NoSessionCookieOrApiKey)) => Ok(Self(None)),
+			Err(error) => Err(error),
+		}
+	}
+}
+
+impl OperationInput for MaybeSession {
+	/// Operation input for the optional session extractor.
+	///
+	/// This adds the same requirements as [`Session`], along with an empty
+	/// requirement to mark authentication as optional.
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		Session::operation_input(ctx, operation);
+		operation.security.push(SecurityRequirement::new());
+	}
+}
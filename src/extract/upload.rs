@@ -0,0 +1,155 @@
+use aide::{
+	openapi::{MediaType, RequestBody, SchemaObject},
+	operation::set_body,
+	OperationInput,
+};
+use axum::{
+	extract::{FromRequest, Multipart, Request},
+	http::StatusCode,
+};
+use bytes::Bytes;
+use schemars::{
+	schema::{InstanceType, Schema},
+	JsonSchema,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use validator::Validate;
+
+use crate::error::AppError;
+
+/// The name of the form field that holds the uploaded file.
+pub const FILE_FIELD: &str = "file";
+
+/// Extractor for a file uploaded as `multipart/form-data`.
+///
+/// The file is read from the `file` field, and every other field
+/// is deserialized into `T` and validated, like the [`Json`](super::Json) extractor.
+///
+/// ```rust
+/// async fn route(upload: Upload<Input>) {
+///   println!("{} is {} bytes", upload.filename, upload.bytes.len());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Upload<T> {
+	/// The name of the file on the client, which may be empty.
+	pub filename: String,
+	pub bytes: Bytes,
+	pub input: T,
+}
+
+fn multipart_error(status: StatusCode, message: String) -> AppError {
+	if status == StatusCode::PAYLOAD_TOO_LARGE {
+		AppError::PayloadTooLarge
+	} else {
+		AppError::Multipart(message)
+	}
+}
+
+#[axum::async_trait]
+impl<T, S> FromRequest<S> for Upload<T>
+where
+	T: DeserializeOwned + Validate,
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+		let mut multipart = Multipart::from_request(req, state)
+			.await
+			.map_err(|rejection| multipart_error(rejection.status(), rejection.body_text()))?;
+
+		let mut file = None;
+		let mut fields = Map::new();
+
+		while let Some(field) = multipart
+			.next_field()
+			.await
+			.map_err(|error| multipart_error(error.status(), error.body_text()))?
+		{
+			let Some(name) = field.name().map(ToOwned::to_owned) else {
+				continue;
+			};
+
+			if name == FILE_FIELD {
+				let filename = field.file_name().unwrap_or_default().to_owned();
+				let bytes = field
+					.bytes()
+					.await
+					.map_err(|error| multipart_error(error.status(), error.body_text()))?;
+
+				file = Some((filename, bytes));
+			} else {
+				let value = field
+					.text()
+					.await
+					.map_err(|error| multipart_error(error.status(), error.body_text()))?;
+
+				fields.insert(name, Value::String(value));
+			}
+		}
+
+		let (filename, bytes) =
+			file.ok_or_else(|| AppError::Multipart(format!("missing `{FILE_FIELD}` field")))?;
+		let input = serde_json::from_value::<T>(Value::Object(fields))
+			.map_err(|error| AppError::Multipart(error.to_string()))?;
+
+		input.validate()?;
+
+		Ok(Self {
+			filename,
+			bytes,
+			input,
+		})
+	}
+}
+
+impl<T> OperationInput for Upload<T>
+where
+	T: JsonSchema,
+{
+	/// Documents the request body as a `multipart/form-data` form with
+	/// the fields of `T`, along with a required binary `file` field.
+	fn operation_input(ctx: &mut aide::gen::GenContext, operation: &mut aide::openapi::Operation) {
+		let mut schema = T::json_schema(&mut ctx.schema).into_object();
+		let file = schemars::schema::SchemaObject {
+			instance_type: Some(InstanceType::String.into()),
+			format: Some("binary".into()),
+			metadata: Some(Box::new(schemars::schema::Metadata {
+				description: Some("The file to upload.".into()),
+				..Default::default()
+			})),
+			..Default::default()
+		};
+
+		let object = schema.object();
+		object
+			.properties
+			.insert(FILE_FIELD.into(), Schema::Object(file));
+		object.required.insert(FILE_FIELD.into());
+
+		set_body(
+			ctx,
+			operation,
+			RequestBody {
+				description: None,
+				content: [(
+					"multipart/form-data".into(),
+					MediaType {
+						schema: Some(SchemaObject {
+							json_schema: Schema::Object(schema),
+							example: None,
+							external_docs: None,
+						}),
+						..Default::default()
+					},
+				)]
+				.into_iter()
+				.collect(),
+				required: true,
+				extensions: Default::default(),
+			},
+		);
+	}
+}
@@ -6,12 +6,19 @@
 #![allow(clippy::enum_glob_use)]
 #![cfg_attr(test, allow(dead_code, unused_imports))]
 
+mod conditional;
 mod error;
 mod extract;
+mod markdown;
+mod media;
 mod openapi;
+mod patch;
 mod ratelimit;
 mod route;
+mod scheduler;
 mod session;
+mod storage;
+mod syndication;
 mod trace;
 
 use std::{net::SocketAddr, sync::Arc, time::Duration};
@@ -56,6 +63,8 @@ pub type Database = sqlx::Pool<sqlx::Postgres>;
 pub struct AppState {
 	pub database: Database,
 	pub hasher: Argon2<'static>,
+	pub storage: Arc<dyn storage::Storage>,
+	pub filters: Arc<route::post::filter::Chain>,
 }
 
 #[tokio::main]
@@ -73,8 +82,16 @@ async fn main() {
 			.await
 			.expect("failed to connect to database"),
 		hasher: Argon2::default(),
+		storage: storage::from_url(env!("STORAGE")).expect("failed to configure storage"),
+		filters: Arc::new(
+			route::post::filter::Chain::from_file(env!("FILTERS"))
+				.expect("failed to load post filters"),
+		),
 	};
 
+	scheduler::spawn(state.database.clone());
+	media::spawn(state.database.clone(), state.storage.clone());
+
 	let port = env!("PORT").parse().expect("PORT must be a number");
 	let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
 		.await
@@ -96,8 +113,15 @@ fn app(state: AppState) -> Router {
 	ratelimit::cleanup_old_limits(&[&default, &secure]);
 
 	let app = ApiRouter::new()
+		.nest("/attachments", route::attachment::routes())
+		.nest("/feed", route::feed::routes())
+		.nest("/me/bookmarks", route::bookmark::routes())
+		.nest("/moderation", route::moderation::routes())
 		.nest("/posts", route::post::routes())
-		.nest("/keys", route::key::routes());
+		.nest("/posts/:id/comments", route::comment::routes())
+		.nest("/keys", route::key::routes())
+		.nest("/tags", route::tag::routes())
+		.nest("/users", route::user::routes());
 
 	#[cfg(not(test))]
 	// All non-secure routes are rate-limited with a more relaxed configuration.
@@ -128,6 +152,7 @@ fn app(state: AppState) -> Router {
 			ServiceBuilder::new()
 				.layer(Extension(Arc::new(openapi)))
 				.compression()
+				.layer(axum::middleware::from_fn(conditional::not_modified))
 				.set_request_id(X_REQUEST_ID, MakeRequestUuid)
 				.layer(
 					TraceLayer::new_for_http()
@@ -177,15 +202,30 @@ mod test {
 	/// It contains various utilities for testing the application, see
 	/// more at [`axum_test`].
 	pub fn app(database: Database) -> TestServer {
+		app_with_filters(database, route::post::filter::Chain::default())
+	}
+
+	/// Constructs a new router like [`app`], which checks posts with the given filters.
+	pub fn app_with_filters(database: Database, filters: route::post::filter::Chain) -> TestServer {
 		let config = TestServerConfig::builder().save_cookies().build();
 		let state = AppState {
 			database,
 			hasher: Argon2::default(),
+			storage: storage(),
+			filters: Arc::new(filters),
 		};
 
 		TestServer::new_with_config(super::app(state), config).unwrap()
 	}
 
+	/// Returns the storage used by test servers, which is shared by all of
+	/// them since objects are stored under unique ids.
+	pub fn storage() -> Arc<dyn storage::Storage> {
+		let root = std::env::temp_dir().join("axum-template-uploads");
+
+		Arc::new(storage::LocalStorage::new(root))
+	}
+
 	#[sqlx::test]
 	async fn test_index(pool: Database) {
 		let app = app(pool);
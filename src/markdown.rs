@@ -0,0 +1,101 @@
+use pulldown_cmark::{html, Event, Options, Parser};
+use validator::ValidationError;
+
+/// The maximum depth of nested blocks and inline elements, such as block quotes
+/// within lists. Deeper elements are rendered as their contents alone.
+pub const MAX_DEPTH: usize = 32;
+
+/// The Markdown extensions on top of `CommonMark`, following GitHub Flavored Markdown.
+fn options() -> Options {
+	Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH
+}
+
+/// Renders Markdown to HTML, which is sanitised against an allowlist of
+/// tags and attributes so it is safe to embed in a page.
+pub fn render(markdown: &str) -> String {
+	let mut depth = 0;
+	let events = Parser::new_ext(markdown, options()).filter(|event| match event {
+		Event::Start(..) => {
+			depth += 1;
+			depth <= MAX_DEPTH
+		}
+		Event::End(..) => {
+			depth -= 1;
+			depth < MAX_DEPTH
+		}
+		_ => true,
+	});
+
+	let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+	html::push_html(&mut unsafe_html, events);
+
+	ammonia::Builder::default()
+		.link_rel(Some("noopener noreferrer nofollow"))
+		.clean(&unsafe_html)
+		.to_string()
+}
+
+/// Returns the depth of the most deeply nested element in Markdown.
+fn depth(markdown: &str) -> usize {
+	let mut depth = 0;
+	let mut max = 0;
+
+	for event in Parser::new_ext(markdown, options()) {
+		match event {
+			Event::Start(..) => {
+				depth += 1;
+				max = max.max(depth);
+			}
+			Event::End(..) => depth -= 1,
+			_ => {}
+		}
+	}
+
+	max
+}
+
+/// Ensures that Markdown content is not nested more deeply than [`MAX_DEPTH`].
+pub fn validate_markdown(markdown: &str) -> Result<(), ValidationError> {
+	if depth(markdown) > MAX_DEPTH {
+		return Err(ValidationError::new("markdown_too_deep"));
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{render, validate_markdown, MAX_DEPTH};
+
+	#[test]
+	fn test_render() {
+		assert_eq!(render("# Hello *world*"), "<h1>Hello <em>world</em></h1>\n");
+		assert_eq!(
+			render("| a |\n|---|\n| ~~b~~ |"),
+			"<table><thead><tr><th>a</th></tr></thead><tbody>\n<tr><td><del>b</del></td></tr>\n</tbody></table>\n"
+		);
+	}
+
+	#[test]
+	fn test_sanitise() {
+		assert_eq!(
+			render("<script>alert(1)</script><b onclick=\"alert(1)\">hi</b>"),
+			"<b>hi</b>"
+		);
+		assert_eq!(
+			render("[x](javascript:alert(1)) [y](https://example.com)"),
+			"<p><a rel=\"noopener noreferrer nofollow\">x</a> <a href=\"https://example.com\" rel=\"noopener noreferrer nofollow\">y</a></p>\n"
+		);
+	}
+
+	#[test]
+	fn test_depth() {
+		let deep = format!("{}deep", "> ".repeat(MAX_DEPTH * 2));
+		let html = render(&deep);
+
+		assert_eq!(html.matches("<blockquote>").count(), MAX_DEPTH);
+		assert!(html.contains("deep"));
+		assert!(validate_markdown(&deep).is_err());
+		assert!(validate_markdown(&"> ".repeat(MAX_DEPTH - 1)).is_ok());
+	}
+}
@@ -0,0 +1,597 @@
+//! Resized variants of uploaded images.
+//!
+//! Images are processed by a background worker instead of while they are uploaded,
+//! so uploads stay fast. Uploads wake the worker with [`wake`], and it also checks for
+//! pending images every [`INTERVAL`], which picks up anything left over from a restart.
+
+use std::{io::Cursor, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use image::{
+	codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+	imageops::FilterType,
+	metadata::Orientation,
+	DynamicImage, ImageDecoder, ImageReader, ImageResult, Limits,
+};
+use sqlx::types::Json as JsonValue;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+	route::attachment::model::{Variant, VariantStatus},
+	storage::{self, Storage},
+	Database,
+};
+
+/// How often pending images are checked for when the worker is not woken up.
+const INTERVAL: Duration = Duration::from_mins(1);
+
+/// The largest width or height of an image that is decoded.
+const MAX_DIMENSION: u32 = 16_384;
+/// The most memory that decoding a single image can use, in bytes.
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// How many times an image is tried before it is marked as failed.
+const MAX_ATTEMPTS: i32 = 5;
+/// How long to wait before retrying an image the first time, which doubles with each attempt.
+const RETRY_DELAY: Duration = INTERVAL;
+
+/// The content types of the images that are processed into variants.
+const IMAGE_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+	/// JPEG for opaque images, and PNG for images with transparency.
+	Auto,
+	/// Lossless WebP.
+	WebP,
+}
+
+/// The configuration of a variant that is produced for every image.
+#[derive(Debug)]
+pub struct VariantConfig {
+	pub name: &'static str,
+	/// The largest width or height of the variant. Images are only ever scaled down.
+	pub max_size: u32,
+	pub format: Format,
+}
+
+pub const VARIANTS: [VariantConfig; 3] = [
+	VariantConfig {
+		name: "thumbnail",
+		max_size: 320,
+		format: Format::Auto,
+	},
+	VariantConfig {
+		name: "medium",
+		max_size: 1280,
+		format: Format::Auto,
+	},
+	VariantConfig {
+		name: "webp",
+		max_size: 2048,
+		format: Format::WebP,
+	},
+];
+
+static WAKE: Notify = Notify::const_new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("database error: {0}")]
+	Database(#[from] sqlx::Error),
+	#[error("storage error: {0}")]
+	Storage(#[from] storage::Error),
+}
+
+/// Returns whether files of `content_type` are processed into variants.
+pub fn is_image(content_type: &str) -> bool {
+	IMAGE_TYPES.contains(&content_type)
+}
+
+/// Returns the storage key of a variant, which is stored next to the original file.
+pub fn key(attachment_id: Uuid, name: &str) -> String {
+	format!("{attachment_id}.{name}")
+}
+
+/// Decodes an image and produces each of the [`VARIANTS`], along with their contents.
+///
+/// The EXIF orientation of the image is applied first, and no metadata
+/// is copied to the variants, which strips any location or camera details.
+pub fn process(bytes: &[u8]) -> ImageResult<Vec<(Variant, Vec<u8>)>> {
+	let mut limits = Limits::default();
+
+	limits.max_image_width = Some(MAX_DIMENSION);
+	limits.max_image_height = Some(MAX_DIMENSION);
+	limits.max_alloc = Some(MAX_ALLOC);
+
+	let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+
+	reader.limits(limits);
+
+	let mut decoder = reader.into_decoder()?;
+	let orientation = decoder.orientation()?;
+	let mut image = DynamicImage::from_decoder(decoder)?;
+
+	image.apply_orientation(orientation);
+
+	VARIANTS
+		.iter()
+		.map(|config| {
+			let resized = if image.width() > config.max_size || image.height() > config.max_size {
+				image.resize(config.max_size, config.max_size, FilterType::CatmullRom)
+			} else {
+				image.clone()
+			};
+
+			let (content_type, bytes) = encode(&resized, config.format)?;
+			let variant = Variant {
+				name: config.name.into(),
+				url: String::new(),
+				content_type: content_type.into(),
+				width: resized.width(),
+				height: resized.height(),
+				size: bytes.len(),
+			};
+
+			Ok((variant, bytes))
+		})
+		.collect()
+}
+
+fn encode(image: &DynamicImage, format: Format) -> ImageResult<(&'static str, Vec<u8>)> {
+	let mut bytes = Vec::new();
+	let alpha = image.color().has_alpha();
+	let content_type = match format {
+		Format::Auto if alpha => {
+			DynamicImage::ImageRgba8(image.to_rgba8())
+				.write_with_encoder(PngEncoder::new(&mut bytes))?;
+			"image/png"
+		}
+		Format::Auto => {
+			DynamicImage::ImageRgb8(image.to_rgb8())
+				.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))?;
+			"image/jpeg"
+		}
+		Format::WebP => {
+			let image = if alpha {
+				DynamicImage::ImageRgba8(image.to_rgba8())
+			} else {
+				DynamicImage::ImageRgb8(image.to_rgb8())
+			};
+
+			image.write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?;
+			"image/webp"
+		}
+	};
+
+	Ok((content_type, bytes))
+}
+
+/// Removes metadata such as location, camera details and comments from an uploaded image,
+/// without re-encoding it. The EXIF orientation of a JPEG is kept, so it still displays
+/// the right way up. Other files, and images that cannot be parsed, are returned as is.
+pub fn strip_metadata(content_type: &str, bytes: Bytes) -> Bytes {
+	let stripped = match content_type {
+		"image/jpeg" => strip_jpeg(&bytes),
+		"image/png" => strip_png(&bytes),
+		"image/webp" => strip_webp(&bytes),
+		_ => None,
+	};
+
+	stripped.map_or(bytes, Bytes::from)
+}
+
+/// Keeps the JFIF (APP0), ICC profile (APP2) and Adobe (APP14) segments of a JPEG,
+/// which affect how it is displayed, and replaces the EXIF segment with one that
+/// only has the orientation.
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+	let mut rest = bytes.strip_prefix(b"\xff\xd8")?;
+	let mut segments = Vec::new();
+	let mut orientation = None;
+
+	// Everything from the start of scan onwards is image data
+	while !rest.starts_with(b"\xff\xda") {
+		let [0xff, marker, high, low, ..] = *rest else {
+			return None;
+		};
+
+		// Markers can be padded with any number of fill bytes
+		if marker == 0xff {
+			rest = &rest[1..];
+			continue;
+		}
+
+		let length = usize::from(u16::from_be_bytes([high, low]));
+		let segment = rest.get(..length + 2).filter(|_| length >= 2)?;
+
+		match marker {
+			0xe1 => {
+				if let Some(exif) = segment[4..].strip_prefix(b"Exif\0\0") {
+					orientation = orientation.or(Orientation::from_exif_chunk(exif));
+				}
+			}
+			0xe0 | 0xe2 | 0xee => segments.push(segment),
+			0xe3..=0xef | 0xfe => {}
+			_ => segments.push(segment),
+		}
+
+		rest = &rest[length + 2..];
+	}
+
+	let mut stripped = b"\xff\xd8".to_vec();
+	let mut segments = segments.into_iter().peekable();
+
+	// JFIF requires its segment to come first
+	if let Some(jfif) = segments.next_if(|segment| segment[1] == 0xe0) {
+		stripped.extend_from_slice(jfif);
+	}
+
+	if let Some(orientation) = orientation.filter(|o| *o != Orientation::NoTransforms) {
+		let exif = exif_orientation(orientation);
+		let length = u16::try_from(exif.len() + 2).ok()?;
+
+		stripped.extend_from_slice(b"\xff\xe1");
+		stripped.extend_from_slice(&length.to_be_bytes());
+		stripped.extend_from_slice(&exif);
+	}
+
+	segments.for_each(|segment| stripped.extend_from_slice(segment));
+	stripped.extend_from_slice(rest);
+
+	Some(stripped)
+}
+
+/// Returns an EXIF segment (big-endian) whose only entry is the orientation.
+fn exif_orientation(orientation: Orientation) -> Vec<u8> {
+	let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0".to_vec();
+
+	exif.extend([orientation.to_exif(), 0, 0, 0, 0, 0, 0]);
+	exif
+}
+
+/// Drops the text, time and EXIF chunks of a PNG.
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+	const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+	let mut rest = bytes.strip_prefix(SIGNATURE)?;
+	let mut stripped = SIGNATURE.to_vec();
+
+	while !rest.is_empty() {
+		let length = usize::try_from(u32::from_be_bytes(rest.get(..4)?.try_into().ok()?)).ok()?;
+		// The length, type, data and checksum of the chunk
+		let chunk = rest.get(..length + 12)?;
+
+		if !matches!(
+			&chunk[4..8],
+			b"tEXt" | b"zTXt" | b"iTXt" | b"tIME" | b"eXIf"
+		) {
+			stripped.extend_from_slice(chunk);
+		}
+
+		rest = &rest[length + 12..];
+	}
+
+	Some(stripped)
+}
+
+/// Drops the EXIF and XMP chunks of a WebP, along with their flags in its header.
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+	const EXIF_FLAG: u8 = 0x08;
+	const XMP_FLAG: u8 = 0x04;
+
+	if bytes.get(..4)? != b"RIFF" || bytes.get(8..12)? != b"WEBP" {
+		return None;
+	}
+
+	let mut rest = &bytes[12..];
+	let mut stripped = bytes[..12].to_vec();
+
+	while !rest.is_empty() {
+		let length = usize::try_from(u32::from_le_bytes(rest.get(4..8)?.try_into().ok()?)).ok()?;
+		// The type, size and data of the chunk, padded to an even length
+		let chunk = rest.get(..(8 + length + length % 2).min(rest.len()))?;
+
+		match &chunk[..4] {
+			b"EXIF" | b"XMP " => {}
+			b"VP8X" => {
+				let offset = stripped.len();
+
+				stripped.extend_from_slice(chunk);
+				*stripped.get_mut(offset + 8)? &= !(EXIF_FLAG | XMP_FLAG);
+			}
+			_ => stripped.extend_from_slice(chunk),
+		}
+
+		rest = &rest[chunk.len()..];
+	}
+
+	let size = u32::try_from(stripped.len() - 8).ok()?;
+
+	stripped[4..8].copy_from_slice(&size.to_le_bytes());
+
+	Some(stripped)
+}
+
+/// Reads an image from storage and stores each of its variants,
+/// returning `None` if the image could not be decoded.
+async fn store_variants(
+	storage: &dyn Storage,
+	id: Uuid,
+) -> Result<Option<Vec<Variant>>, storage::Error> {
+	let bytes = storage.get(&id.to_string(), None).await?;
+	// Decoding and encoding are CPU-bound, so they are kept off the async runtime
+	let result = tokio::task::spawn_blocking(move || process(&bytes))
+		.await
+		.map_err(|error| error.to_string())
+		.and_then(|result| result.map_err(|error| error.to_string()));
+
+	let outputs = match result {
+		Ok(outputs) => outputs,
+		Err(error) => {
+			tracing::warn!("failed to process image {id}: {error}");
+			return Ok(None);
+		}
+	};
+
+	let mut variants = Vec::with_capacity(outputs.len());
+
+	for (variant, bytes) in outputs {
+		storage
+			.put(&key(id, &variant.name), Bytes::from(bytes))
+			.await?;
+		variants.push(variant);
+	}
+
+	Ok(Some(variants))
+}
+
+/// Processes every pending image, returning the number of images that were processed.
+///
+/// Images that cannot be decoded, or whose file is missing, are marked as failed.
+/// Other storage errors leave the image pending, to be retried after a delay that
+/// doubles with each attempt, until it is marked as failed after [`MAX_ATTEMPTS`].
+pub async fn process_pending(database: &Database, storage: &dyn Storage) -> Result<u64, Error> {
+	let mut count = 0;
+
+	loop {
+		let mut tx = database.begin().await?;
+
+		// Locked until the variants are stored, so concurrent workers skip the image
+		let Some(id) = sqlx::query_scalar!(
+			r#"
+				SELECT id FROM attachment
+				WHERE variant_status = 'pending'
+					AND (variant_retry_at IS NULL OR variant_retry_at <= now())
+				ORDER BY created_at
+				LIMIT 1
+				FOR UPDATE SKIP LOCKED
+			"#
+		)
+		.fetch_optional(&mut *tx)
+		.await?
+		else {
+			return Ok(count);
+		};
+
+		let (status, variants) = match store_variants(storage, id).await {
+			Ok(Some(variants)) => (VariantStatus::Ready, variants),
+			Ok(None) => (VariantStatus::Failed, Vec::new()),
+			Err(storage::Error::NotFound) => {
+				tracing::warn!("failed to process image {id}: file not found");
+				(VariantStatus::Failed, Vec::new())
+			}
+			Err(error) => {
+				tracing::warn!("failed to process image {id}, retrying later: {error}");
+				sqlx::query!(
+					r#"
+						UPDATE attachment
+						SET
+							variant_attempts = variant_attempts + 1,
+							variant_retry_at = now() + make_interval(secs => $2 * power(2, variant_attempts)),
+							variant_status = CASE
+								WHEN variant_attempts + 1 >= $3 THEN 'failed'
+								ELSE variant_status
+							END
+						WHERE id = $1
+					"#,
+					id,
+					RETRY_DELAY.as_secs_f64(),
+					MAX_ATTEMPTS,
+				)
+				.execute(&mut *tx)
+				.await?;
+
+				tx.commit().await?;
+				continue;
+			}
+		};
+
+		sqlx::query!(
+			"UPDATE attachment SET variant_status = $2, variants = $3 WHERE id = $1",
+			id,
+			status as VariantStatus,
+			JsonValue(&variants) as _,
+		)
+		.execute(&mut *tx)
+		.await?;
+
+		tx.commit().await?;
+		count += 1;
+	}
+}
+
+/// Wakes the worker up to process a newly uploaded image.
+pub fn wake() {
+	WAKE.notify_one();
+}
+
+/// Processes pending images in the background whenever the worker is woken up.
+pub fn spawn(database: Database, storage: Arc<dyn Storage>) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(INTERVAL);
+
+		loop {
+			tokio::select! {
+				() = WAKE.notified() => {}
+				_ = interval.tick() => {}
+			}
+
+			match process_pending(&database, &*storage).await {
+				Ok(0) => {}
+				Ok(count) => tracing::info!("processed {count} image(s)"),
+				Err(error) => tracing::error!("failed to process images: {error}"),
+			}
+		}
+	});
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::Cursor;
+
+	use bytes::Bytes;
+	use image::{
+		codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+		ImageEncoder, RgbImage, RgbaImage,
+	};
+
+	use super::{process, strip_metadata};
+
+	/// A minimal EXIF block (big-endian) whose only entry is `orientation`.
+	fn exif(orientation: u8) -> Vec<u8> {
+		let mut exif = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0".to_vec();
+
+		exif.extend([orientation, 0, 0, 0, 0, 0, 0]);
+		exif
+	}
+
+	#[test]
+	fn test_process() {
+		let mut bytes = Vec::new();
+		let mut encoder = JpegEncoder::new(Cursor::new(&mut bytes));
+
+		encoder.set_exif_metadata(exif(6)).unwrap();
+		encoder
+			.write_image(
+				RgbImage::new(1600, 1200).as_raw(),
+				1600,
+				1200,
+				image::ExtendedColorType::Rgb8,
+			)
+			.unwrap();
+
+		assert!(bytes.windows(4).any(|window| window == b"Exif"));
+
+		let variants = process(&bytes).unwrap();
+		let sizes = variants
+			.iter()
+			.map(|(variant, _)| (variant.name.as_str(), variant.width, variant.height))
+			.collect::<Vec<_>>();
+
+		// rotated by the orientation, and never scaled up
+		assert_eq!(
+			sizes,
+			[
+				("thumbnail", 240, 320),
+				("medium", 960, 1280),
+				("webp", 1200, 1600)
+			]
+		);
+
+		for (variant, bytes) in &variants {
+			assert_eq!(variant.size, bytes.len());
+			assert!(!bytes.windows(4).any(|window| window == b"Exif"));
+		}
+
+		assert_eq!(variants[0].0.content_type, "image/jpeg");
+		assert_eq!(variants[2].0.content_type, "image/webp");
+	}
+
+	#[test]
+	fn test_strip_metadata() {
+		let mut exif = exif(6);
+
+		exif.extend_from_slice(b"GPS secret");
+
+		let mut jpeg = Vec::new();
+		let mut encoder = JpegEncoder::new(Cursor::new(&mut jpeg));
+
+		encoder.set_exif_metadata(exif.clone()).unwrap();
+		encoder
+			.write_image(
+				RgbImage::new(40, 30).as_raw(),
+				40,
+				30,
+				image::ExtendedColorType::Rgb8,
+			)
+			.unwrap();
+
+		let mut png = Vec::new();
+		let mut encoder = PngEncoder::new(Cursor::new(&mut png));
+
+		encoder.set_exif_metadata(exif.clone()).unwrap();
+		encoder
+			.write_image(
+				RgbImage::new(40, 30).as_raw(),
+				40,
+				30,
+				image::ExtendedColorType::Rgb8,
+			)
+			.unwrap();
+
+		let mut webp = Vec::new();
+		let mut encoder = WebPEncoder::new_lossless(Cursor::new(&mut webp));
+
+		encoder.set_exif_metadata(exif).unwrap();
+		encoder
+			.write_image(
+				RgbImage::new(40, 30).as_raw(),
+				40,
+				30,
+				image::ExtendedColorType::Rgb8,
+			)
+			.unwrap();
+
+		for (content_type, bytes) in [
+			("image/jpeg", jpeg),
+			("image/png", png),
+			("image/webp", webp),
+		] {
+			assert!(bytes.windows(6).any(|window| window == b"secret"));
+
+			let stripped = strip_metadata(content_type, Bytes::from(bytes));
+
+			assert!(!stripped.windows(6).any(|window| window == b"secret"));
+
+			// still a valid image, and only JPEGs keep their orientation
+			let variants = process(&stripped).unwrap();
+			let rotated = content_type == "image/jpeg";
+
+			assert_eq!(
+				(variants[0].0.width, variants[0].0.height),
+				if rotated { (30, 40) } else { (40, 30) },
+			);
+		}
+
+		let text = Bytes::from_static(b"not an image");
+
+		assert_eq!(strip_metadata("image/jpeg", text.clone()), text);
+	}
+
+	#[test]
+	fn test_process_transparent() {
+		let mut bytes = Vec::new();
+
+		RgbaImage::new(10, 20)
+			.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+			.unwrap();
+
+		let variants = process(&bytes).unwrap();
+
+		assert_eq!(variants[0].0.content_type, "image/png");
+		assert_eq!((variants[0].0.width, variants[0].0.height), (10, 20));
+		assert!(process(b"\x89PNG\r\n\x1a\nnot an image").is_err());
+	}
+}
@@ -15,9 +15,15 @@ use axum::{
 use crate::{error, extract::Json, session};
 
 pub mod tag {
+	pub const ATTACHMENT: &str = "Attachment";
 	pub const AUTH: &str = "Auth";
 	pub const POST: &str = "Post";
+	pub const COMMENT: &str = "Comment";
+	pub const FEED: &str = "Feed";
 	pub const KEY: &str = "Key";
+	pub const MODERATION: &str = "Moderation";
+	pub const TAG: &str = "Tag";
+	pub const USER: &str = "User";
 }
 
 pub fn routes() -> ApiRouter {
@@ -49,6 +55,36 @@ pub fn docs(api: TransformOpenApi) -> TransformOpenApi {
 			description: Some("Post management".into()),
 			..Default::default()
 		})
+		.tag(Tag {
+			name: tag::COMMENT.into(),
+			description: Some("Comments on posts".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: tag::ATTACHMENT.into(),
+			description: Some("Files uploaded by users and attached to posts".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: tag::TAG.into(),
+			description: Some("Post tags".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: tag::USER.into(),
+			description: Some("Users and their posts".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: tag::FEED.into(),
+			description: Some("Posts of the users you follow".into()),
+			..Default::default()
+		})
+		.tag(Tag {
+			name: tag::MODERATION.into(),
+			description: Some("Reported posts, for admins".into()),
+			..Default::default()
+		})
 		.tag(Tag {
 			name: tag::KEY.into(),
 			description: Some("API key management".into()),
@@ -0,0 +1,202 @@
+use std::{borrow::Cow, marker::PhantomData};
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use validator::{ValidateEmail, ValidateLength};
+
+/// A field of an update, which distinguishes a missing field from one
+/// that is explicitly set to `null`, following JSON Merge Patch (RFC 7396).
+///
+/// Missing fields leave the current value unchanged, `null` clears it,
+/// and any other value replaces it. Fields must be marked with `#[serde(default)]`
+/// for a missing field to deserialize, which the `#[model]` macro does for
+/// the generated `UpdateX` structs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Patch<T> {
+	/// The field was not present, so it is left unchanged.
+	#[default]
+	Missing,
+	/// The field was `null`, so it is cleared.
+	Null,
+	/// The field was set to a new value.
+	Value(T),
+}
+
+impl<T> Patch<T> {
+	pub fn is_missing(&self) -> bool {
+		matches!(self, Self::Missing)
+	}
+
+	/// Returns the new value, if one was given.
+	pub fn as_value(&self) -> Option<&T> {
+		match self {
+			Self::Value(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Returns the new value, if one was given.
+	pub fn value(self) -> Option<T> {
+		match self {
+			Self::Value(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Applies the patch to the current value of a nullable field.
+	pub fn apply(self, current: Option<T>) -> Option<T> {
+		match self {
+			Self::Missing => current,
+			Self::Null => None,
+			Self::Value(value) => Some(value),
+		}
+	}
+
+	/// Applies the patch to the current value of a required field.
+	///
+	/// Required fields reject `null` when deserializing, so it is treated like a missing field.
+	pub fn unwrap_or(self, current: T) -> T {
+		self.value().unwrap_or(current)
+	}
+}
+
+impl<'de, T> Deserialize<'de> for Patch<T>
+where
+	T: Deserialize<'de>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		// Missing fields never reach this point, they use `Default` instead
+		Ok(Option::<T>::deserialize(deserializer)?.map_or(Self::Null, Self::Value))
+	}
+}
+
+/// Deserializes a field that cannot be cleared, rejecting `null`.
+pub fn deserialize_required<'de, D, T>(deserializer: D) -> Result<Patch<T>, D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de>,
+{
+	T::deserialize(deserializer).map(Patch::Value)
+}
+
+impl<T> Serialize for Patch<T>
+where
+	T: Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		self.as_value().serialize(serializer)
+	}
+}
+
+impl<T> JsonSchema for Patch<T>
+where
+	T: JsonSchema,
+{
+	fn is_referenceable() -> bool {
+		false
+	}
+
+	fn schema_name() -> String {
+		Option::<T>::schema_name()
+	}
+
+	fn schema_id() -> Cow<'static, str> {
+		Option::<T>::schema_id()
+	}
+
+	fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+		Option::<T>::json_schema(gen)
+	}
+
+	// Never listed as a required property, even without a default
+	fn _schemars_private_is_option() -> bool {
+		true
+	}
+}
+
+/// The JSON schema of a [`Patch`] field that cannot be cleared, which is that of `T` without `null`.
+///
+/// This is only used in `#[schemars(with = "...")]`, and never constructed.
+#[allow(dead_code)]
+pub struct Required<T>(PhantomData<T>);
+
+impl<T> JsonSchema for Required<T>
+where
+	T: JsonSchema,
+{
+	fn is_referenceable() -> bool {
+		false
+	}
+
+	fn schema_name() -> String {
+		T::schema_name()
+	}
+
+	fn schema_id() -> Cow<'static, str> {
+		T::schema_id()
+	}
+
+	fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+		gen.subschema_for::<T>()
+	}
+
+	fn _schemars_private_is_option() -> bool {
+		true
+	}
+}
+
+impl<T> ValidateLength<u64> for Patch<T>
+where
+	T: ValidateLength<u64>,
+{
+	fn length(&self) -> Option<u64> {
+		self.as_value().and_then(T::length)
+	}
+}
+
+impl<T> ValidateEmail for Patch<T>
+where
+	T: ValidateEmail,
+{
+	fn as_email_string(&self) -> Option<Cow<'_, str>> {
+		self.as_value().and_then(T::as_email_string)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[derive(Deserialize)]
+	struct Update {
+		#[serde(default)]
+		published_at: Patch<String>,
+	}
+
+	#[test]
+	fn test_deserialize() {
+		let update = |json| serde_json::from_str::<Update>(json).unwrap().published_at;
+
+		assert_eq!(update("{}"), Patch::Missing);
+		assert_eq!(update(r#"{"published_at":null}"#), Patch::Null);
+		assert_eq!(
+			update(r#"{"published_at":"now"}"#),
+			Patch::Value("now".into())
+		);
+	}
+
+	#[test]
+	fn test_apply() {
+		let current = Some(1);
+
+		assert_eq!(Patch::Missing.apply(current), Some(1));
+		assert_eq!(Patch::Null.apply(current), None);
+		assert_eq!(Patch::Value(2).apply(current), Some(2));
+	}
+}
@@ -60,6 +60,8 @@ where
 		.iter()
 		.map(|config| config.limiter().clone())
 		.collect::<Vec<_>>();
+	// `Duration::from_mins` is too new for the toolchains that the crate supports
+	#[allow(unknown_lints, clippy::duration_suboptimal_units)]
 	let interval = Duration::from_secs(60);
 
 	std::thread::spawn(move || loop {
@@ -0,0 +1,434 @@
+use aide::axum::{routing::get_with, ApiRouter};
+use axum::{
+	extract::DefaultBodyLimit,
+	http::{header, HeaderMap, HeaderValue, StatusCode},
+};
+use uuid::Uuid;
+
+use crate::{error, AppState};
+
+pub mod model;
+pub mod route;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("attachment_not_found")]
+	UnknownAttachment(Uuid),
+	#[error("post_not_found")]
+	UnknownPost(Uuid),
+	#[error("variant_not_found")]
+	UnknownVariant(String),
+	#[error("empty_attachment")]
+	Empty,
+	#[error("attachment_too_large")]
+	TooLarge(usize),
+	#[error("quota_exceeded")]
+	QuotaExceeded(i64),
+	#[error("range_not_satisfiable")]
+	RangeNotSatisfiable(usize),
+}
+
+pub type RouteError = error::RouteError<Error>;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new()
+		.api_route(
+			"/",
+			get_with(get_attachments, get_attachments_docs)
+				.post_with(upload_attachment, upload_attachment_docs),
+		)
+		.api_route(
+			"/:id",
+			get_with(get_attachment, get_attachment_docs)
+				.delete_with(delete_attachment, delete_attachment_docs),
+		)
+		.api_route(
+			"/:id/content",
+			get_with(download_attachment, download_attachment_docs),
+		)
+		.api_route(
+			"/:id/variants/:name",
+			get_with(download_variant, download_variant_docs),
+		)
+		// Leaves room for the rest of the form, so oversized files get a helpful error
+		.layer(DefaultBodyLimit::max(model::MAX_SIZE + 64 * 1024))
+}
+
+impl error::ErrorShape for Error {
+	fn status(&self) -> StatusCode {
+		match self {
+			Self::UnknownAttachment(..) | Self::UnknownPost(..) | Self::UnknownVariant(..) => {
+				StatusCode::NOT_FOUND
+			}
+			Self::Empty => StatusCode::BAD_REQUEST,
+			Self::TooLarge(..) | Self::QuotaExceeded(..) => StatusCode::PAYLOAD_TOO_LARGE,
+			Self::RangeNotSatisfiable(..) => StatusCode::RANGE_NOT_SATISFIABLE,
+		}
+	}
+
+	fn headers(&self) -> Option<HeaderMap> {
+		let Self::RangeNotSatisfiable(size) = self else {
+			return None;
+		};
+
+		let range = HeaderValue::try_from(format!("bytes */{size}")).ok()?;
+
+		Some([(header::CONTENT_RANGE, range)].into_iter().collect())
+	}
+
+	fn into_errors(self) -> Vec<error::Message<'static>> {
+		let message = match self {
+			Self::UnknownAttachment(..) => "The attachment you provided does not exist.",
+			Self::UnknownPost(..) => "The post you provided does not exist.",
+			Self::UnknownVariant(..) => "The variant does not exist, or is not ready yet.",
+			Self::Empty => "The file is empty.",
+			Self::TooLarge(..) => "The file is too large.",
+			Self::QuotaExceeded(..) => "You do not have enough space left for this file.",
+			Self::RangeNotSatisfiable(..) => "The requested range is outside of the file.",
+		};
+
+		let message = error::Message::new(self.to_string()).content(message);
+
+		match self {
+			Self::UnknownAttachment(key) => message.detail("attachment", key.to_string()),
+			Self::UnknownPost(key) => message.detail("key", key.to_string()),
+			Self::UnknownVariant(name) => message.detail("variant", name),
+			Self::Empty => message,
+			Self::TooLarge(max) => message.detail("max", max),
+			Self::QuotaExceeded(quota) => message.detail("quota", quota),
+			Self::RangeNotSatisfiable(size) => message.detail("size", size),
+		}
+		.into_vec()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use axum::http::{header, StatusCode};
+	use axum_test::multipart::{MultipartForm, Part};
+
+	use crate::test::*;
+
+	const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01";
+
+	#[sqlx::test]
+	async fn test_attachments(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (app, username) in [(&app, "john"), (&other, "jane")] {
+			app.post("/auth/register")
+				.json(&json!({
+					"email": format!("{username}@smith.com"),
+					"username": username,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "first", "content": "", "status": "draft" }))
+			.await
+			.json::<serde_json::Value>();
+		let post_id = post["id"].as_str().unwrap();
+
+		// the client's content type is ignored in favour of the contents
+		let form = MultipartForm::new().add_text("post_id", post_id).add_part(
+			"file",
+			Part::bytes(PNG)
+				.file_name("../pixel.png")
+				.mime_type("text/html"),
+		);
+		let attachment = app
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(attachment["filename"], "pixel.png");
+		assert_eq!(attachment["content_type"], "image/png");
+		assert_eq!(attachment["size"], PNG.len());
+		assert_eq!(attachment["post_id"], post_id);
+
+		let path = format!(
+			"/attachments/{}/content",
+			attachment["id"].as_str().unwrap()
+		);
+		let response = app.get(&path).await;
+
+		assert_eq!(response.as_bytes().as_ref(), PNG);
+		assert_eq!(response.header(header::CONTENT_TYPE), "image/png");
+		assert_eq!(
+			response.header(header::CONTENT_DISPOSITION),
+			"inline; filename=\"pixel.png\"; filename*=UTF-8''pixel.png"
+		);
+
+		let response = app
+			.get(&path)
+			.add_header(header::RANGE, "bytes=1-3".parse().unwrap())
+			.await;
+
+		response.assert_status(StatusCode::PARTIAL_CONTENT);
+		assert_eq!(response.as_bytes().as_ref(), b"PNG");
+		assert_eq!(
+			response.header(header::CONTENT_RANGE),
+			format!("bytes 1-3/{}", PNG.len())
+		);
+
+		let response = app
+			.get(&path)
+			.add_header(header::RANGE, "bytes=100-".parse().unwrap())
+			.await;
+
+		response.assert_status(StatusCode::RANGE_NOT_SATISFIABLE);
+		assert_eq!(
+			response.header(header::CONTENT_RANGE),
+			format!("bytes */{}", PNG.len())
+		);
+
+		// files of unpublished posts are only visible to their owner
+		other.get(&path).await.assert_status_not_found();
+
+		app.put(&format!("/posts/{post_id}"))
+			.json(&json!({ "status": "published" }))
+			.await
+			.assert_status_ok();
+
+		other.get(&path).await.assert_status_ok();
+
+		let form = MultipartForm::new().add_text("post_id", post_id).add_part(
+			"file",
+			Part::bytes(b"hello".as_slice()).file_name("hello.txt"),
+		);
+
+		other
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.assert_status_not_found();
+
+		let form = MultipartForm::new().add_part(
+			"file",
+			Part::bytes(b"<svg onload=\"alert(1)\"></svg>".as_slice()).file_name("x.svg"),
+		);
+		let attachment = other
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.json::<serde_json::Value>();
+		let response = other
+			.get(&format!(
+				"/attachments/{}/content",
+				attachment["id"].as_str().unwrap()
+			))
+			.await;
+
+		assert!(response
+			.header(header::CONTENT_DISPOSITION)
+			.to_str()
+			.unwrap()
+			.starts_with("attachment;"));
+
+		let page = app.get("/attachments").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+
+		let response = app
+			.post("/attachments")
+			.multipart(MultipartForm::new().add_text("post_id", post_id))
+			.await;
+
+		response.assert_status_bad_request();
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"invalid_multipart"
+		);
+
+		let big = vec![b'a'; super::model::MAX_SIZE + 1];
+		let response = app
+			.post("/attachments")
+			.multipart(MultipartForm::new().add_part("file", Part::bytes(big)))
+			.await;
+
+		response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+
+		app.delete(&path.replace("/content", ""))
+			.await
+			.assert_status_ok();
+		app.get(&path).await.assert_status_not_found();
+	}
+
+	#[sqlx::test]
+	async fn test_variants(pool: Database) {
+		let app = app(pool.clone());
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let mut jpeg = std::io::Cursor::new(Vec::new());
+
+		image::RgbImage::new(800, 400)
+			.write_to(&mut jpeg, image::ImageFormat::Jpeg)
+			.unwrap();
+
+		let form = MultipartForm::new()
+			.add_part("file", Part::bytes(jpeg.into_inner()).file_name("wide.jpg"));
+		let attachment = app
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.json::<serde_json::Value>();
+		let path = format!("/attachments/{}", attachment["id"].as_str().unwrap());
+
+		assert_eq!(attachment["variant_status"], "pending");
+		assert_eq!(attachment["variants"], json!([]));
+
+		app.get(&format!("{path}/variants/thumbnail"))
+			.await
+			.assert_status_not_found();
+
+		let processed = crate::media::process_pending(&pool, &*storage())
+			.await
+			.unwrap();
+
+		assert_eq!(processed, 1);
+
+		let attachment = app.get(&path).await.json::<serde_json::Value>();
+		let thumbnail = &attachment["variants"][0];
+
+		assert_eq!(attachment["variant_status"], "ready");
+		assert_eq!(attachment["variants"].as_array().unwrap().len(), 3);
+		assert_eq!(thumbnail["name"], "thumbnail");
+		assert_eq!(thumbnail["url"], format!("{path}/variants/thumbnail"));
+		assert_eq!(
+			(&thumbnail["width"], &thumbnail["height"]),
+			(&json!(320), &json!(160))
+		);
+
+		let response = app.get(thumbnail["url"].as_str().unwrap()).await;
+
+		response.assert_status_ok();
+		assert_eq!(response.header(header::CONTENT_TYPE), "image/jpeg");
+		assert_eq!(response.as_bytes().len(), thumbnail["size"]);
+
+		// files other than images have no variants
+		let form = MultipartForm::new().add_part(
+			"file",
+			Part::bytes(b"hello".as_slice()).file_name("hello.txt"),
+		);
+		let attachment = app
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.json::<serde_json::Value>();
+
+		assert!(attachment.get("variant_status").is_none());
+
+		app.delete(&path).await.assert_status_ok();
+		app.get(&format!("{path}/variants/thumbnail"))
+			.await
+			.assert_status_not_found();
+	}
+
+	/// A storage backend whose objects can never be read.
+	struct Unavailable;
+
+	#[axum::async_trait]
+	impl crate::storage::Storage for Unavailable {
+		async fn put(&self, _key: &str, _bytes: bytes::Bytes) -> Result<(), crate::storage::Error> {
+			Err(std::io::Error::other("unavailable").into())
+		}
+
+		async fn get(
+			&self,
+			_key: &str,
+			_range: Option<std::ops::Range<usize>>,
+		) -> Result<bytes::Bytes, crate::storage::Error> {
+			Err(std::io::Error::other("unavailable").into())
+		}
+
+		async fn delete(&self, _key: &str) -> Result<(), crate::storage::Error> {
+			Ok(())
+		}
+	}
+
+	#[sqlx::test]
+	async fn test_variant_retries(pool: Database) {
+		let app = app(pool.clone());
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let mut png = std::io::Cursor::new(Vec::new());
+
+		image::RgbImage::new(20, 10)
+			.write_to(&mut png, image::ImageFormat::Png)
+			.unwrap();
+
+		let png = png.into_inner();
+		let mut ids = Vec::new();
+
+		for name in ["missing.png", "image.png"] {
+			let form =
+				MultipartForm::new().add_part("file", Part::bytes(png.clone()).file_name(name));
+			let attachment = app
+				.post("/attachments")
+				.multipart(form)
+				.await
+				.json::<serde_json::Value>();
+
+			ids.push(attachment["id"].as_str().unwrap().to_owned());
+		}
+
+		// images whose file is missing fail without holding up the rest of the queue
+		storage().delete(&ids[0]).await.unwrap();
+
+		let processed = crate::media::process_pending(&pool, &*storage())
+			.await
+			.unwrap();
+
+		assert_eq!(processed, 2);
+
+		for (id, status) in ids.iter().zip(["failed", "ready"]) {
+			let attachment = app
+				.get(&format!("/attachments/{id}"))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(attachment["variant_status"], status);
+		}
+
+		// other storage errors are retried later
+		let form = MultipartForm::new().add_part("file", Part::bytes(png).file_name("later.png"));
+		let attachment = app
+			.post("/attachments")
+			.multipart(form)
+			.await
+			.json::<serde_json::Value>();
+		let path = format!("/attachments/{}", attachment["id"].as_str().unwrap());
+
+		for backend in [&Unavailable as &dyn crate::storage::Storage, &*storage()] {
+			let processed = crate::media::process_pending(&pool, backend).await.unwrap();
+
+			assert_eq!(processed, 0);
+		}
+
+		assert_eq!(
+			app.get(&path).await.json::<serde_json::Value>()["variant_status"],
+			"pending"
+		);
+	}
+}
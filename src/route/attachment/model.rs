@@ -0,0 +1,313 @@
+pub use crate::route::model::{IdInput, Keyset, SortKey};
+
+use std::ops::Range;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+	error::BoxDynError,
+	postgres::{PgTypeInfo, PgValueRef},
+	types::Json as JsonValue,
+	Decode, Postgres,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+/// The maximum size of a single file, in bytes.
+pub const MAX_SIZE: usize = 10 * 1024 * 1024;
+/// The maximum total size of the files of a user, in bytes.
+pub const QUOTA: i64 = 100 * 1024 * 1024;
+
+/// Content types that are safe to display inline. Everything else,
+/// including SVG images (which can contain scripts), is only downloaded.
+const INLINE_TYPES: [&str; 5] = [
+	"image/png",
+	"image/jpeg",
+	"image/gif",
+	"image/webp",
+	"image/avif",
+];
+
+/// A file uploaded by a user, which can be attached to one of their posts.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Attachment {
+	/// The unique identifier of the attachment.
+	pub id: Uuid,
+	/// The user that uploaded the file.
+	pub user_id: Uuid,
+	/// The post that the file is attached to, if any.
+	pub post_id: Option<Uuid>,
+	/// The name of the file, without any directories.
+	pub filename: String,
+	/// The content type of the file, detected from its contents.
+	pub content_type: String,
+	/// The size of the file in bytes.
+	pub size: i64,
+	/// Whether the resized variants of an image are ready, which is omitted for other files.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub variant_status: Option<VariantStatus>,
+	/// The resized variants of an image, once they are ready.
+	pub variants: Variants,
+	/// The time the file was uploaded.
+	pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Attachment {
+	/// The columns selected for an [`Attachment`] in dynamic queries.
+	pub const SELECT: &'static str =
+		"SELECT id, user_id, post_id, filename, content_type, size, variant_status, variants, created_at";
+
+	/// Fills in the URL of each variant, which is not stored.
+	pub fn fill_urls(&mut self) {
+		for variant in &mut self.variants.0 {
+			variant.url = format!("/attachments/{}/variants/{}", self.id, variant.name);
+		}
+	}
+
+	/// Returns the `Content-Disposition` header for downloading the file, which
+	/// displays images inline and downloads everything else.
+	pub fn content_disposition(&self) -> String {
+		let kind = if INLINE_TYPES.contains(&self.content_type.as_str()) {
+			"inline"
+		} else {
+			"attachment"
+		};
+
+		// The plain `filename` is a fallback for clients without RFC 6266 support
+		let fallback = self
+			.filename
+			.chars()
+			.map(|c| match c {
+				'"' | '\\' => '_',
+				c if c == ' ' || c.is_ascii_graphic() => c,
+				_ => '_',
+			})
+			.collect::<String>();
+		let encoded = self
+			.filename
+			.bytes()
+			.map(|b| match b {
+				b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' => {
+					char::from(b).to_string()
+				}
+				_ => format!("%{b:02X}"),
+			})
+			.collect::<String>();
+
+		format!("{kind}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+	}
+}
+
+impl Keyset for Attachment {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.created_at)
+	}
+}
+
+/// The progress of resizing an image into its variants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "variant_status", rename_all = "snake_case")]
+pub enum VariantStatus {
+	/// The image is waiting to be processed.
+	Pending,
+	/// The variants are ready.
+	Ready,
+	/// The image could not be decoded, so it has no variants.
+	Failed,
+}
+
+/// A resized copy of an image, without any of its metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct Variant {
+	/// The name of the variant, such as `thumbnail`.
+	pub name: String,
+	/// The URL to download the variant from.
+	#[serde(skip_deserializing)]
+	pub url: String,
+	/// The content type of the variant.
+	pub content_type: String,
+	/// The width of the variant in pixels.
+	pub width: u32,
+	/// The height of the variant in pixels.
+	pub height: u32,
+	/// The size of the variant in bytes.
+	pub size: usize,
+}
+
+/// The variants of an image, in the order they are configured.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Variants(pub Vec<Variant>);
+
+impl Variants {
+	pub fn get(&self, name: &str) -> Option<&Variant> {
+		self.0.iter().find(|variant| variant.name == name)
+	}
+}
+
+// Stored as a JSONB array in `attachment.variants`, while the URLs are filled in by `Attachment::fill_urls`
+impl sqlx::Type<Postgres> for Variants {
+	fn type_info() -> PgTypeInfo {
+		JsonValue::<Vec<Variant>>::type_info()
+	}
+
+	fn compatible(ty: &PgTypeInfo) -> bool {
+		JsonValue::<Vec<Variant>>::compatible(ty)
+	}
+}
+
+impl<'r> Decode<'r, Postgres> for Variants {
+	fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+		JsonValue::<Vec<Variant>>::decode(value).map(|json| Self(json.0))
+	}
+}
+
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct UploadInput {
+	/// The post to attach the file to, which must be one of your posts.
+	pub post_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct VariantInput {
+	/// The unique identifier of the attachment.
+	pub id: Uuid,
+	/// The name of the variant, such as `thumbnail`.
+	pub name: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct FilterInput {
+	/// Only return the files attached to this post.
+	pub post_id: Option<Uuid>,
+}
+
+/// Returns the name of an uploaded file without any directories or control
+/// characters, so it is safe to send back in a header.
+pub fn sanitize_filename(name: &str) -> String {
+	let name = name
+		.rsplit(['/', '\\'])
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.filter(|c| !c.is_control())
+		.take(255)
+		.collect::<String>();
+	let name = name.trim().trim_start_matches('.');
+
+	if name.is_empty() {
+		"file".into()
+	} else {
+		name.into()
+	}
+}
+
+/// Detects the content type of a file from its contents, ignoring the type
+/// claimed by the client. Unknown files are plain text if they are valid UTF-8.
+pub fn sniff(bytes: &[u8]) -> &'static str {
+	match infer::get(bytes) {
+		Some(kind) => kind.mime_type(),
+		None if std::str::from_utf8(bytes).is_ok() => "text/plain; charset=utf-8",
+		None => "application/octet-stream",
+	}
+}
+
+/// The part of a file requested with a `Range` header.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+	/// The whole file, when there is no `Range` header or it is not supported.
+	Full,
+	/// A single range of bytes within the file.
+	Partial(Range<usize>),
+	/// A range that starts after the end of the file.
+	Unsatisfiable,
+}
+
+impl ByteRange {
+	/// Parses a `Range` header for a file of `size` bytes (RFC 9110, section 14.2).
+	///
+	/// Only a single range is supported. Headers with several ranges or an
+	/// invalid syntax are ignored, which serves the whole file instead.
+	pub fn parse(header: Option<&str>, size: usize) -> Self {
+		let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+			return Self::Full;
+		};
+
+		let Some((start, end)) = spec.trim().split_once('-') else {
+			return Self::Full;
+		};
+
+		let range = if start.is_empty() {
+			// bytes=-500 is the last 500 bytes
+			match end.parse::<usize>() {
+				Ok(0) => return Self::Unsatisfiable,
+				Ok(suffix) => size.saturating_sub(suffix)..size,
+				Err(..) => return Self::Full,
+			}
+		} else {
+			let Ok(start) = start.parse::<usize>() else {
+				return Self::Full;
+			};
+
+			if end.is_empty() {
+				start..size
+			} else {
+				match end.parse::<usize>() {
+					Ok(end) if start <= end => start..size.min(end.saturating_add(1)),
+					_ => return Self::Full,
+				}
+			}
+		};
+
+		if range.start >= size {
+			Self::Unsatisfiable
+		} else {
+			Self::Partial(range)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{sanitize_filename, sniff, ByteRange};
+
+	#[test]
+	fn test_byte_range() {
+		let parse = |header| ByteRange::parse(Some(header), 100);
+
+		assert_eq!(ByteRange::parse(None, 100), ByteRange::Full);
+		assert_eq!(parse("bytes=0-9"), ByteRange::Partial(0..10));
+		assert_eq!(parse("bytes=90-"), ByteRange::Partial(90..100));
+		assert_eq!(parse("bytes=-10"), ByteRange::Partial(90..100));
+		assert_eq!(parse("bytes=-200"), ByteRange::Partial(0..100));
+		assert_eq!(parse("bytes=50-500"), ByteRange::Partial(50..100));
+		assert_eq!(parse("bytes=100-"), ByteRange::Unsatisfiable);
+		assert_eq!(parse("bytes=-0"), ByteRange::Unsatisfiable);
+		assert_eq!(parse("bytes=0-1,5-6"), ByteRange::Full);
+		assert_eq!(parse("bytes=9-0"), ByteRange::Full);
+		assert_eq!(parse("items=0-9"), ByteRange::Full);
+	}
+
+	#[test]
+	fn test_sanitize_filename() {
+		assert_eq!(sanitize_filename("photo.png"), "photo.png");
+		assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+		assert_eq!(sanitize_filename("C:\\Users\\me\\cv.pdf"), "cv.pdf");
+		assert_eq!(sanitize_filename("a\r\nb.txt"), "ab.txt");
+		assert_eq!(sanitize_filename(".env"), "env");
+		assert_eq!(sanitize_filename(""), "file");
+	}
+
+	#[test]
+	fn test_sniff() {
+		assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), "image/png");
+		assert_eq!(sniff(b"%PDF-1.7"), "application/pdf");
+		assert_eq!(sniff("héllo".as_bytes()), "text/plain; charset=utf-8");
+		assert_eq!(sniff(b"\xff\xfe\x00"), "application/octet-stream");
+	}
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use axum::{
+	extract::State,
+	http::{header, HeaderMap, HeaderValue, StatusCode},
+	response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use macros::route;
+use uuid::Uuid;
+
+use crate::{
+	conditional::http_date,
+	extract::{Json, MaybeSession, Page, Pagination, Path, Query, Session, Upload},
+	media,
+	openapi::tag,
+	route::model::Sort,
+	storage::Storage,
+	Database,
+};
+
+use super::{model, Error, RouteError};
+
+fn header_value(value: String) -> HeaderValue {
+	HeaderValue::try_from(value).expect("attachment headers are ASCII")
+}
+
+/// Inserts the headers shared by the contents of files and their variants.
+fn content_headers(
+	headers: &mut HeaderMap,
+	content_type: &str,
+	disposition: String,
+	etag: String,
+	created_at: DateTime<Utc>,
+) {
+	headers.insert(header::CONTENT_TYPE, header_value(content_type.into()));
+	headers.insert(header::CONTENT_DISPOSITION, header_value(disposition));
+	headers.insert(
+		header::X_CONTENT_TYPE_OPTIONS,
+		HeaderValue::from_static("nosniff"),
+	);
+	headers.insert(
+		header::CONTENT_SECURITY_POLICY,
+		HeaderValue::from_static("default-src 'none'; sandbox"),
+	);
+	// Files never change, so the id identifies the contents
+	headers.insert(header::ETAG, header_value(etag));
+	headers.insert(header::LAST_MODIFIED, http_date(created_at));
+}
+
+/// Fetches an attachment if it is visible to the viewer, which is the
+/// case for their own files and files attached to posts they can read.
+async fn fetch_attachment(
+	database: &Database,
+	attachment_id: Uuid,
+	viewer: Option<Uuid>,
+) -> Result<model::Attachment, RouteError> {
+	let mut attachment = sqlx::query_as!(
+		model::Attachment,
+		r#"
+			SELECT
+				id, user_id, post_id, filename, content_type, size,
+				variant_status AS "variant_status: model::VariantStatus",
+				variants AS "variants: model::Variants",
+				created_at
+			FROM attachment
+			WHERE id = $1 AND (
+				user_id = $2
+				OR EXISTS (
+					SELECT 1 FROM post
+					WHERE id = attachment.post_id AND post_readable(post.user_id, status, visibility, hidden, $2)
+				)
+			)
+		"#,
+		attachment_id,
+		viewer,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownAttachment(attachment_id))?;
+
+	attachment.fill_urls();
+
+	Ok(attachment)
+}
+
+/// Get own attachments
+/// Returns a paginated response of the files you uploaded, newest first.
+#[route(tag = tag::ATTACHMENT)]
+pub async fn get_attachments(
+	State(database): State<Database>,
+	session: Session,
+	Query(filter): Query<model::FilterInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Attachment>, RouteError> {
+	let mut attachments = pagination
+		.fetch(
+			&database,
+			model::Attachment::SELECT,
+			Sort::NEWEST,
+			|query| {
+				query
+					.push(" FROM attachment WHERE user_id = ")
+					.push_bind(session.user.id);
+
+				if let Some(post_id) = filter.post_id {
+					query.push(" AND post_id = ").push_bind(post_id);
+				}
+			},
+		)
+		.await?;
+
+	attachments
+		.items
+		.iter_mut()
+		.for_each(model::Attachment::fill_urls);
+
+	Ok(attachments)
+}
+
+/// Upload attachment
+/// Uploads a file as `multipart/form-data`, optionally attaching it to one of your posts.
+///
+/// Files can be up to 10 MiB, and each user can store up to 100 MiB in total.
+/// The content type is detected from the contents of the file. Images are resized
+/// into variants in the background, which are listed once `variant_status` is `ready`.
+#[route(
+	tag = tag::ATTACHMENT,
+	response(status = 413, description = "The file is too large, or you have no space left.")
+)]
+pub async fn upload_attachment(
+	State(database): State<Database>,
+	State(storage): State<Arc<dyn Storage>>,
+	session: Session,
+	upload: Upload<model::UploadInput>,
+) -> Result<Json<model::Attachment>, RouteError> {
+	if upload.bytes.is_empty() {
+		return Err(Error::Empty.into());
+	}
+
+	if upload.bytes.len() > model::MAX_SIZE {
+		return Err(Error::TooLarge(model::MAX_SIZE).into());
+	}
+
+	let content_type = model::sniff(&upload.bytes);
+	// Images are served as uploaded, so their location and camera details are removed first
+	let bytes = media::strip_metadata(content_type, upload.bytes);
+	let size = i64::try_from(bytes.len()).unwrap_or(i64::MAX);
+	let mut tx = database.begin().await?;
+
+	// Uploads of the same user are serialised, so they cannot exceed the quota together
+	sqlx::query!(
+		r#"SELECT id FROM "user" WHERE id = $1 FOR UPDATE"#,
+		session.user.id
+	)
+	.fetch_one(&mut *tx)
+	.await?;
+
+	if let Some(post_id) = upload.input.post_id {
+		sqlx::query_scalar!(
+			"SELECT id FROM post WHERE id = $1 AND user_id = $2",
+			post_id,
+			session.user.id,
+		)
+		.fetch_optional(&mut *tx)
+		.await?
+		.ok_or(Error::UnknownPost(post_id))?;
+	}
+
+	let used = sqlx::query_scalar!(
+		r#"SELECT COALESCE(SUM(size), 0)::bigint AS "used!" FROM attachment WHERE user_id = $1"#,
+		session.user.id,
+	)
+	.fetch_one(&mut *tx)
+	.await?;
+
+	if used + size > model::QUOTA {
+		return Err(Error::QuotaExceeded(model::QUOTA).into());
+	}
+
+	let variant_status = media::is_image(content_type).then_some(model::VariantStatus::Pending);
+	let attachment = sqlx::query_as!(
+		model::Attachment,
+		r#"
+			INSERT INTO attachment (user_id, post_id, filename, content_type, size, variant_status)
+			VALUES ($1, $2, $3, $4, $5, $6)
+			RETURNING
+				id, user_id, post_id, filename, content_type, size,
+				variant_status AS "variant_status: model::VariantStatus",
+				variants AS "variants: model::Variants",
+				created_at
+		"#,
+		session.user.id,
+		upload.input.post_id,
+		model::sanitize_filename(&upload.filename),
+		content_type,
+		size,
+		variant_status as Option<model::VariantStatus>,
+	)
+	.fetch_one(&mut *tx)
+	.await?;
+
+	// The row is only committed once the file is stored
+	storage.put(&attachment.id.to_string(), bytes).await?;
+	tx.commit().await?;
+
+	if variant_status.is_some() {
+		media::wake();
+	}
+
+	Ok(Json(attachment))
+}
+
+/// Get attachment
+/// Returns the details of a file. Files are visible to their owner,
+/// and to everyone who can read the post they are attached to.
+#[route(tag = tag::ATTACHMENT)]
+pub async fn get_attachment(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::IdInput>,
+) -> Result<Json<model::Attachment>, RouteError> {
+	let attachment = fetch_attachment(&database, path.id, session.user_id()).await?;
+
+	Ok(Json(attachment))
+}
+
+/// Download attachment
+/// Returns the contents of a file. Images are displayed inline, and other files are downloaded.
+///
+/// A single byte range can be requested with the `Range` header, such as `bytes=0-1023`.
+#[route(
+	tag = tag::ATTACHMENT,
+	response(status = 200, shape = "Vec<u8>"),
+	response(status = 206, description = "The requested range of the file."),
+	response(status = 416, description = "The requested range is outside of the file.")
+)]
+pub async fn download_attachment(
+	State(database): State<Database>,
+	State(storage): State<Arc<dyn Storage>>,
+	session: MaybeSession,
+	Path(path): Path<model::IdInput>,
+	headers: HeaderMap,
+) -> Result<Response, RouteError> {
+	let attachment = fetch_attachment(&database, path.id, session.user_id()).await?;
+	let size = usize::try_from(attachment.size).unwrap_or_default();
+	let range = headers
+		.get(header::RANGE)
+		.and_then(|value| value.to_str().ok());
+
+	let (status, range) = match model::ByteRange::parse(range, size) {
+		model::ByteRange::Full => (StatusCode::OK, None),
+		model::ByteRange::Partial(range) => (StatusCode::PARTIAL_CONTENT, Some(range)),
+		model::ByteRange::Unsatisfiable => return Err(Error::RangeNotSatisfiable(size).into()),
+	};
+
+	let bytes = storage
+		.get(&attachment.id.to_string(), range.clone())
+		.await?;
+
+	let mut response = (status, bytes).into_response();
+	let headers = response.headers_mut();
+
+	content_headers(
+		headers,
+		&attachment.content_type,
+		attachment.content_disposition(),
+		format!("\"{}\"", attachment.id),
+		attachment.created_at,
+	);
+	headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+	if let Some(range) = range {
+		let content_range = format!("bytes {}-{}/{size}", range.start, range.end - 1);
+
+		headers.insert(header::CONTENT_RANGE, header_value(content_range));
+	}
+
+	Ok(response)
+}
+
+/// Download attachment variant
+/// Returns the contents of a resized variant of an image, such as its `thumbnail`.
+#[route(tag = tag::ATTACHMENT, response(status = 200, shape = "Vec<u8>"))]
+pub async fn download_variant(
+	State(database): State<Database>,
+	State(storage): State<Arc<dyn Storage>>,
+	session: MaybeSession,
+	Path(path): Path<model::VariantInput>,
+) -> Result<Response, RouteError> {
+	let attachment = fetch_attachment(&database, path.id, session.user_id()).await?;
+	let variant = attachment
+		.variants
+		.get(&path.name)
+		.ok_or_else(|| Error::UnknownVariant(path.name.clone()))?;
+
+	let bytes = storage
+		.get(&media::key(attachment.id, &variant.name), None)
+		.await?;
+
+	let mut response = bytes.into_response();
+
+	content_headers(
+		response.headers_mut(),
+		&variant.content_type,
+		"inline".into(),
+		format!("\"{}.{}\"", attachment.id, variant.name),
+		attachment.created_at,
+	);
+
+	Ok(response)
+}
+
+/// Delete attachment
+/// Deletes one of your files, along with its variants.
+#[route(tag = tag::ATTACHMENT)]
+pub async fn delete_attachment(
+	State(database): State<Database>,
+	State(storage): State<Arc<dyn Storage>>,
+	session: Session,
+	Path(path): Path<model::IdInput>,
+) -> Result<(), RouteError> {
+	sqlx::query_scalar!(
+		"DELETE FROM attachment WHERE id = $1 AND user_id = $2 RETURNING id",
+		path.id,
+		session.user.id,
+	)
+	.fetch_optional(&database)
+	.await?
+	.ok_or(Error::UnknownAttachment(path.id))?;
+
+	// The attachment is gone once its row is, so files that fail to delete are only left behind
+	let keys = media::VARIANTS
+		.iter()
+		.map(|variant| media::key(path.id, variant.name));
+
+	for key in std::iter::once(path.id.to_string()).chain(keys) {
+		if let Err(error) = storage.delete(&key).await {
+			tracing::warn!(
+				"failed to delete file {key} of attachment {}: {error}",
+				path.id
+			);
+		}
+	}
+
+	Ok(())
+}
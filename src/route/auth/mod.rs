@@ -27,6 +27,8 @@ pub enum Error {
 	InvalidSessionCookie,
 	#[error("invalid_api_key")]
 	InvalidApiKey,
+	#[error("admin_required")]
+	AdminRequired,
 	#[error("username_taken")]
 	UsernameTaken,
 	#[error("email_taken")]
@@ -46,6 +48,7 @@ pub fn routes() -> ApiRouter<AppState> {
 			"/me",
 			get_with(get_me, get_me_docs)
 				.put_with(update_me, update_me_docs)
+				.patch_with(patch_me, patch_me_docs)
 				.delete_with(delete_me, delete_me_docs),
 		)
 }
@@ -57,6 +60,7 @@ impl error::ErrorShape for Error {
 			| Self::NoSessionCookieOrApiKey
 			| Self::InvalidSessionCookie
 			| Self::InvalidApiKey => StatusCode::UNAUTHORIZED,
+			Self::AdminRequired => StatusCode::FORBIDDEN,
 			Self::Argon(..) | Self::Cookie(..) => StatusCode::INTERNAL_SERVER_ERROR,
 			Self::UsernameTaken | Self::EmailTaken => StatusCode::CONFLICT,
 		}
@@ -72,6 +76,7 @@ impl error::ErrorShape for Error {
 			NoSessionCookieOrApiKey => "An authentication cookie or API key is required.",
 			InvalidSessionCookie => "The provided session cookie is invalid.",
 			InvalidApiKey => "The provided API key is invalid.",
+			AdminRequired => "Only admins can do this.",
 			UsernameTaken => "The provided username is already taken.",
 			EmailTaken => "The provided email is already taken.",
 		};
@@ -129,4 +134,105 @@ mod test {
 
 		assert_eq!(response.json::<serde_json::Value>()["username"], "john");
 	}
+
+	#[sqlx::test]
+	async fn test_update_me_if_match(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let etag = app.get("/auth/me").await.header("etag");
+
+		let response = app
+			.put("/auth/me")
+			.add_header("if-match".parse().unwrap(), etag.clone())
+			.json(&json!({ "email": "john@example.com" }))
+			.await;
+
+		assert_eq!(response.status_code(), 200);
+		assert_ne!(response.header("etag"), etag);
+
+		let response = app
+			.put("/auth/me")
+			.add_header("if-match".parse().unwrap(), etag)
+			.json(&json!({ "username": "johnny" }))
+			.await;
+
+		assert_eq!(response.status_code(), 412);
+		assert_eq!(
+			app.get("/auth/me").await.json::<serde_json::Value>()["username"],
+			"john"
+		);
+	}
+
+	#[sqlx::test]
+	async fn test_patch_me(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let response = app
+			.patch("/auth/me")
+			.bytes(
+				json!([{ "op": "replace", "path": "/username", "value": "johnny" }])
+					.to_string()
+					.into(),
+			)
+			.content_type("application/json-patch+json")
+			.await;
+
+		assert_eq!(response.status_code(), 200);
+		assert_eq!(response.json::<serde_json::Value>()["username"], "johnny");
+
+		let response = app
+			.patch("/auth/me")
+			.bytes(json!({ "username": null }).to_string().into())
+			.content_type("application/merge-patch+json")
+			.await;
+
+		assert_eq!(response.status_code(), 400);
+	}
+
+	#[sqlx::test]
+	async fn test_optional_session(pool: Database) {
+		let app = app(pool);
+
+		app.get("/posts").await.assert_status_ok();
+
+		let response = app
+			.get("/posts")
+			.add_header(
+				"authorization".parse().unwrap(),
+				format!("Bearer {}", uuid::Uuid::nil()).parse().unwrap(),
+			)
+			.await;
+
+		assert_eq!(response.status_code(), 401);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"invalid_api_key"
+		);
+
+		let response = app
+			.get("/posts")
+			.add_header(
+				"cookie".parse().unwrap(),
+				"session=invalid".parse().unwrap(),
+			)
+			.await;
+
+		assert_eq!(response.status_code(), 401);
+	}
 }
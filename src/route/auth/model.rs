@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
 
+use crate::extract::{ETag, Json, Tagged};
+
 fn validate_username(username: &str) -> Result<(), ValidationError> {
 	if username.chars().any(|c| !c.is_alphanumeric()) {
 		return Err(ValidationError::new("username must be alphanumeric"));
@@ -30,9 +32,30 @@ pub struct User {
 	/// The username that is displayed to the public.
 	#[validate(length(min = 3, max = 16), custom(function = "validate_username"))]
 	pub username: String,
+	/// Whether the user is an admin, who can moderate reported posts.
+	#[serde(skip_deserializing)]
+	pub is_admin: bool,
 	/// The creation time of the user.
 	#[serde(skip_deserializing)]
 	pub created_at: chrono::DateTime<chrono::Utc>,
+	/// The last time the user was changed.
+	#[serde(skip_deserializing)]
+	pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl User {
+	/// Returns the entity tag of the user, which also covers the email
+	/// address since it can be updated but is never serialized.
+	pub fn etag(&self) -> ETag {
+		ETag::of(&(self, &self.email))
+	}
+
+	/// Responds with the user as JSON, along with its `ETag` and `Last-Modified` headers.
+	pub fn tagged(self) -> Tagged<Json<Self>> {
+		let updated_at = self.updated_at;
+
+		Tagged::new(self.etag(), Json(self)).last_modified(updated_at)
+	}
 }
 
 #[derive(Serialize, Validate, JsonSchema)]
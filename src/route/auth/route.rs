@@ -6,10 +6,11 @@ use axum::{
 	response::IntoResponse,
 };
 use macros::route;
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::{
-	extract::{Json, Session, SessionOrApiKey},
+	extract::{IfMatch, Json, PatchRequest, Session, SessionOrApiKey, Tagged},
 	openapi::tag,
 	session, AppState, Database,
 };
@@ -144,19 +145,81 @@ pub async fn register(
 
 /// Get user
 /// Returns the authenticated user.
-#[route(tag = tag::AUTH)]
-pub async fn get_me(session: Session) -> Json<model::User> {
-	Json(session.user)
+#[route(
+	tag = tag::AUTH,
+	response(status = 304, description = "The user has not changed since it was fetched.")
+)]
+pub async fn get_me(session: Session) -> Tagged<Json<model::User>> {
+	session.user.tagged()
 }
 
 /// Update user
 /// Updates the authenticated user.
-#[route(tag = tag::AUTH)]
+///
+/// Send the `ETag` of the user in `If-Match` to avoid overwriting changes made since you fetched it.
+#[route(
+	tag = tag::AUTH,
+	response(status = 412, description = "The user has changed since it was fetched.")
+)]
 pub async fn update_me(
 	State(state): State<AppState>,
 	session: Session,
+	if_match: IfMatch,
 	Json(auth): Json<model::UpdateUser>,
-) -> Result<Json<model::User>, RouteError> {
+) -> Result<Tagged<Json<model::User>>, RouteError> {
+	update(
+		&state.database,
+		session.user.id,
+		&if_match,
+		PatchRequest::Merge(auth),
+	)
+	.await
+}
+
+/// Patch user
+/// Partially updates the authenticated user, with either a JSON Merge Patch
+/// (`application/merge-patch+json`) or a JSON Patch (`application/json-patch+json`).
+///
+/// A JSON Patch is applied to an object with the `email` and `username` of the user.
+#[route(
+	tag = tag::AUTH,
+	response(status = 412, description = "The user has changed since it was fetched."),
+	response(status = 415, description = "The request body is not a supported patch format."),
+	response(status = 422, description = "The JSON Patch could not be applied to the user.")
+)]
+pub async fn patch_me(
+	State(state): State<AppState>,
+	session: Session,
+	if_match: IfMatch,
+	patch: PatchRequest<model::UpdateUser>,
+) -> Result<Tagged<Json<model::User>>, RouteError> {
+	update(&state.database, session.user.id, &if_match, patch).await
+}
+
+/// Updates a user if it still matches `If-Match`.
+async fn update(
+	database: &Database,
+	user_id: Uuid,
+	if_match: &IfMatch,
+	patch: PatchRequest<model::UpdateUser>,
+) -> Result<Tagged<Json<model::User>>, RouteError> {
+	let mut tx = database.begin().await?;
+
+	let current = sqlx::query_as!(
+		model::User,
+		r#"SELECT * FROM "user" WHERE id = $1 FOR UPDATE"#,
+		user_id
+	)
+	.fetch_one(&mut *tx)
+	.await?;
+
+	if_match.check(&current.etag())?;
+
+	// The email is never serialized, so the patch is applied to a view that includes it
+	let auth = patch
+		.resolve(&json!({ "email": current.email, "username": current.username }))
+		.map_err(RouteError::App)?;
+
 	let user = sqlx::query_as!(
 		model::User,
 		r#"
@@ -165,14 +228,16 @@ pub async fn update_me(
 			WHERE id = $3
 			RETURNING *
 		"#,
-		auth.email,
-		auth.username,
-		session.user.id
+		auth.email.value(),
+		auth.username.value(),
+		user_id
 	)
-	.fetch_one(&state.database)
+	.fetch_one(&mut *tx)
 	.await?;
 
-	Ok(Json(user))
+	tx.commit().await?;
+
+	Ok(user.tagged())
 }
 
 /// Delete user
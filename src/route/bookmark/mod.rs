@@ -0,0 +1,105 @@
+use aide::axum::{routing::get_with, ApiRouter};
+
+use crate::AppState;
+
+pub mod route;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new().api_route("/", get_with(get_bookmarks, get_bookmarks_docs))
+}
+
+#[cfg(test)]
+mod test {
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_bookmarks(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let mut ids = Vec::new();
+
+		for title in ["first", "second", "third"] {
+			let post = other
+				.post("/posts")
+				.json(&json!({ "title": title, "content": "" }))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(post["bookmarked"], false);
+			ids.push(post["id"].as_str().unwrap().to_string());
+		}
+
+		// bookmarking twice has no effect
+		for id in [&ids[1], &ids[0], &ids[2], &ids[1]] {
+			let post = app
+				.put(&format!("/posts/{id}/bookmark"))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(post["bookmarked"], true);
+		}
+
+		let page = app.get("/me/bookmarks").await.json::<serde_json::Value>();
+		let titles = page["items"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|post| post["title"].as_str().unwrap())
+			.collect::<Vec<_>>();
+
+		assert_eq!(titles, ["third", "first", "second"]);
+		assert_eq!(page["items"][0]["bookmarked"], true);
+		assert!(page["items"][0]["bookmarked_at"].is_string());
+
+		let page = app.get("/posts").await.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["bookmarked"], true);
+
+		let post = other
+			.get(&format!("/posts/{}", ids[0]))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["bookmarked"], false);
+
+		// deleted and hidden posts drop out of the list
+		other
+			.delete(&format!("/posts/{}", ids[0]))
+			.await
+			.assert_status_ok();
+		other
+			.put(&format!("/posts/{}", ids[1]))
+			.json(&json!({ "visibility": "private" }))
+			.await
+			.assert_status_ok();
+
+		let page = app.get("/me/bookmarks").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+
+		let post = app
+			.delete(&format!("/posts/{}/bookmark", ids[2]))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["bookmarked"], false);
+
+		app.put(&format!("/posts/{}/bookmark", ids[1]))
+			.await
+			.assert_status_not_found();
+	}
+}
@@ -0,0 +1,65 @@
+use axum::extract::State;
+use macros::route;
+
+use crate::{
+	extract::{Page, Pagination, Query, Session},
+	openapi::tag,
+	route::{
+		model::Sort,
+		post::{self, model, RouteError},
+	},
+	Database,
+};
+
+/// Get bookmarks
+/// Returns a paginated response of the posts you bookmarked, most recently bookmarked first.
+/// Bookmarks of posts that were deleted or are now hidden from you are left out.
+#[route(tag = tag::POST)]
+pub async fn get_bookmarks(
+	State(database): State<Database>,
+	session: Session,
+	Query(render): Query<model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Bookmark>, RouteError> {
+	let viewer = session.user.id;
+	let mut bookmarks =
+		pagination
+			.fetch(
+				&database,
+				model::Bookmark::SELECT,
+				Sort::descending("bookmarked_at"),
+				|query| {
+					query
+					.push(" FROM (SELECT post.*, bookmark.created_at AS bookmarked_at FROM bookmark")
+					.push(" JOIN post ON post.id = bookmark.post_id WHERE bookmark.user_id = ")
+					.push_bind(viewer)
+					.push(") post WHERE post_readable(user_id, status, visibility, hidden, ")
+					.push_bind(viewer)
+					.push(")");
+				},
+			)
+			.await?;
+
+	post::route::set_viewer_state(
+		&database,
+		Some(viewer),
+		bookmarks
+			.items
+			.iter_mut()
+			.map(|bookmark: &mut model::Bookmark| &mut bookmark.post),
+	)
+	.await?;
+
+	if render.html() {
+		post::route::render_html(
+			&database,
+			bookmarks
+				.items
+				.iter_mut()
+				.map(|bookmark| &mut bookmark.post),
+		)
+		.await?;
+	}
+
+	Ok(bookmarks)
+}
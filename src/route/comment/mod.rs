@@ -0,0 +1,183 @@
+use aide::axum::{
+	routing::{get_with, put_with},
+	ApiRouter,
+};
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use crate::{error, AppState};
+
+pub mod model;
+pub mod route;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("post_not_found")]
+	UnknownPost(Uuid),
+	#[error("comment_not_found")]
+	UnknownComment(Uuid),
+	#[error("nested_reply")]
+	NestedReply(Uuid),
+	#[error("comment_forbidden")]
+	Forbidden(Uuid),
+}
+
+pub type RouteError = error::RouteError<Error>;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new()
+		.api_route(
+			"/",
+			get_with(get_comments, get_comments_docs)
+				.post_with(create_comment, create_comment_docs),
+		)
+		.api_route(
+			"/:comment_id",
+			put_with(update_comment, update_comment_docs)
+				.delete_with(delete_comment, delete_comment_docs),
+		)
+		.api_route(
+			"/:comment_id/replies",
+			get_with(get_replies, get_replies_docs).post_with(create_reply, create_reply_docs),
+		)
+}
+
+impl error::ErrorShape for Error {
+	fn status(&self) -> StatusCode {
+		match self {
+			Self::UnknownPost(..) | Self::UnknownComment(..) => StatusCode::NOT_FOUND,
+			Self::NestedReply(..) => StatusCode::BAD_REQUEST,
+			Self::Forbidden(..) => StatusCode::FORBIDDEN,
+		}
+	}
+
+	fn into_errors(self) -> Vec<error::Message<'static>> {
+		let message = match self {
+			Self::UnknownPost(..) => "The post you provided does not exist.",
+			Self::UnknownComment(..) => "The comment you provided does not exist.",
+			Self::NestedReply(..) => "Replies cannot be replied to.",
+			Self::Forbidden(..) => "You are not allowed to change this comment.",
+		};
+
+		let message = error::Message::new(self.to_string()).content(message);
+
+		match self {
+			Self::UnknownPost(key) => message.detail("key", key.to_string()),
+			Self::UnknownComment(key) | Self::NestedReply(key) | Self::Forbidden(key) => {
+				message.detail("comment", key.to_string())
+			}
+		}
+		.into_vec()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use axum::http::StatusCode;
+
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_comments(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (app, username) in [(&app, "john"), (&other, "jane")] {
+			app.post("/auth/register")
+				.json(&json!({
+					"email": format!("{username}@smith.com"),
+					"username": username,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "first", "content": "" }))
+			.await
+			.json::<serde_json::Value>();
+		let path = format!("/posts/{}/comments", post["id"].as_str().unwrap());
+
+		let comment = other
+			.post(&path)
+			.json(&json!({ "content": "Nice post!" }))
+			.await
+			.json::<serde_json::Value>();
+		let comment_path = format!("{path}/{}", comment["id"].as_str().unwrap());
+
+		let reply = app
+			.post(&format!("{comment_path}/replies"))
+			.json(&json!({ "content": "Thanks!" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(reply["parent_id"], comment["id"]);
+
+		// Replies are only one level deep
+		let response = other
+			.post(&format!("{path}/{}/replies", reply["id"].as_str().unwrap()))
+			.json(&json!({ "content": "You're welcome" }))
+			.await;
+
+		response.assert_status(StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"nested_reply"
+		);
+
+		let comments = app.get(&path).await.json::<serde_json::Value>();
+
+		assert_eq!(comments["total"], 1);
+		assert_eq!(comments["items"][0]["reply_count"], 1);
+
+		let replies = app
+			.get(&format!("{comment_path}/replies"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(replies["items"][0]["content"], "Thanks!");
+
+		let post_path = format!("/posts/{}", post["id"].as_str().unwrap());
+
+		let commented = app.get(&post_path).await.json::<serde_json::Value>();
+
+		// Comments are counted without counting as edits to the post
+		assert_eq!(commented["comment_count"], 2);
+		assert_eq!(commented["updated_at"], post["updated_at"]);
+
+		// Only the author can edit a comment
+		app.put(&comment_path)
+			.json(&json!({ "content": "Edited" }))
+			.await
+			.assert_status(StatusCode::FORBIDDEN);
+
+		let edited = other
+			.put(&comment_path)
+			.json(&json!({ "content": "Great post!" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(edited["content"], "Great post!");
+
+		// The post author can delete any comment, which removes its replies too
+		app.delete(&comment_path).await.assert_status_ok();
+
+		assert_eq!(
+			app.get(&post_path).await.json::<serde_json::Value>()["comment_count"],
+			0
+		);
+
+		other
+			.post(&path)
+			.json(&json!({ "content": "Again" }))
+			.await
+			.assert_status_ok();
+
+		app.delete(&post_path).await.assert_status_ok();
+
+		other.get(&path).await.assert_status_not_found();
+	}
+}
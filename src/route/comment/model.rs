@@ -0,0 +1,63 @@
+pub use crate::route::model::{IdInput, Keyset, SortKey};
+
+use macros::model;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// A comment on a post, or a reply to another comment.
+#[model]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, sqlx::FromRow)]
+pub struct Comment {
+	/// The unique identifier of the comment.
+	#[serde(skip_deserializing)]
+	pub id: Uuid,
+	/// The post that the comment is on.
+	#[serde(skip_deserializing)]
+	pub post_id: Uuid,
+	/// The user that wrote the comment.
+	#[serde(skip_deserializing)]
+	pub user_id: Uuid,
+	/// The comment that this is a reply to, or `null` for a top-level comment.
+	/// Replies cannot be replied to themselves.
+	#[serde(skip_deserializing)]
+	pub parent_id: Option<Uuid>,
+	/// The content of the comment in Markdown format.
+	#[validate(length(min = 1, max = 4096))]
+	pub content: String,
+	/// The number of replies to the comment, which is always 0 for replies.
+	#[serde(skip_deserializing)]
+	pub reply_count: i64,
+	/// The creation time of the comment.
+	#[serde(skip_deserializing)]
+	pub created_at: chrono::DateTime<chrono::Utc>,
+	/// The last time the comment was edited.
+	#[serde(skip_deserializing)]
+	pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Comment {
+	/// The columns selected for a [`Comment`] in dynamic queries.
+	pub const SELECT: &'static str =
+		"SELECT id, post_id, user_id, parent_id, content, created_at, updated_at, \
+		(SELECT COUNT(*) FROM comment reply WHERE reply.parent_id = comment.id) AS reply_count";
+}
+
+impl Keyset for Comment {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.created_at)
+	}
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct CommentInput {
+	/// The unique identifier of the post.
+	pub id: Uuid,
+	/// The unique identifier of the comment.
+	pub comment_id: Uuid,
+}
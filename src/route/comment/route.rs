@@ -0,0 +1,229 @@
+use axum::extract::State;
+use macros::route;
+use uuid::Uuid;
+
+use crate::{
+	extract::{Json, MaybeSession, Page, Pagination, Path, Session},
+	openapi::tag,
+	route::model::Sort,
+	Database,
+};
+
+use super::{model, Error, RouteError};
+
+/// Comments are listed in the order they were written.
+const OLDEST: Sort = Sort::ascending("created_at");
+
+/// Returns the author of a post, or an error if the post does not exist
+/// or is not visible to the viewer.
+async fn fetch_author(
+	database: &Database,
+	post_id: Uuid,
+	viewer: Option<Uuid>,
+) -> Result<Uuid, RouteError> {
+	let author = sqlx::query_scalar!(
+		"SELECT user_id FROM post WHERE id = $1 AND post_readable(user_id, status, visibility, hidden, $2)",
+		post_id,
+		viewer,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownPost(post_id))?;
+
+	Ok(author)
+}
+
+/// Fetches a comment on a post.
+async fn fetch_comment(
+	database: &Database,
+	post_id: Uuid,
+	comment_id: Uuid,
+) -> Result<model::Comment, RouteError> {
+	let comment = sqlx::query_as!(
+		model::Comment,
+		r#"
+			SELECT
+				id, post_id, user_id, parent_id, content, created_at, updated_at,
+				(SELECT COUNT(*) FROM comment reply WHERE reply.parent_id = comment.id) AS "reply_count!"
+			FROM comment
+			WHERE id = $1 AND post_id = $2
+		"#,
+		comment_id,
+		post_id,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownComment(comment_id))?;
+
+	Ok(comment)
+}
+
+/// Writes a comment, or a reply if `parent_id` is given.
+async fn insert(
+	database: &Database,
+	post_id: Uuid,
+	user_id: Uuid,
+	parent_id: Option<Uuid>,
+	input: model::CreateComment,
+) -> Result<model::Comment, RouteError> {
+	let comment = sqlx::query_as!(
+		model::Comment,
+		r#"
+			INSERT INTO comment (post_id, user_id, parent_id, content)
+			VALUES ($1, $2, $3, $4)
+			RETURNING
+				id, post_id, user_id, parent_id, content, created_at, updated_at,
+				0::bigint AS "reply_count!"
+		"#,
+		post_id,
+		user_id,
+		parent_id,
+		input.content,
+	)
+	.fetch_one(database)
+	.await?;
+
+	Ok(comment)
+}
+
+/// Get comments
+/// Returns a paginated response of the top-level comments on a post, oldest first.
+/// The replies to each comment are listed separately.
+#[route(tag = tag::COMMENT)]
+pub async fn get_comments(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::IdInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Comment>, RouteError> {
+	fetch_author(&database, path.id, session.user_id()).await?;
+
+	let comments = pagination
+		.fetch(&database, model::Comment::SELECT, OLDEST, |query| {
+			query
+				.push(" FROM comment WHERE parent_id IS NULL AND post_id = ")
+				.push_bind(path.id);
+		})
+		.await?;
+
+	Ok(comments)
+}
+
+/// Create comment
+/// Writes a top-level comment on a post.
+#[route(tag = tag::COMMENT)]
+pub async fn create_comment(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::IdInput>,
+	Json(input): Json<model::CreateComment>,
+) -> Result<Json<model::Comment>, RouteError> {
+	fetch_author(&database, path.id, Some(session.user.id)).await?;
+
+	let comment = insert(&database, path.id, session.user.id, None, input).await?;
+
+	Ok(Json(comment))
+}
+
+/// Get replies
+/// Returns a paginated response of the replies to a comment, oldest first.
+#[route(tag = tag::COMMENT)]
+pub async fn get_replies(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::CommentInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Comment>, RouteError> {
+	fetch_author(&database, path.id, session.user_id()).await?;
+	fetch_comment(&database, path.id, path.comment_id).await?;
+
+	let replies = pagination
+		.fetch(&database, model::Comment::SELECT, OLDEST, |query| {
+			query
+				.push(" FROM comment WHERE parent_id = ")
+				.push_bind(path.comment_id);
+		})
+		.await?;
+
+	Ok(replies)
+}
+
+/// Reply to comment
+/// Writes a reply to a top-level comment. Replies cannot be replied to.
+#[route(tag = tag::COMMENT)]
+pub async fn create_reply(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::CommentInput>,
+	Json(input): Json<model::CreateComment>,
+) -> Result<Json<model::Comment>, RouteError> {
+	fetch_author(&database, path.id, Some(session.user.id)).await?;
+
+	let parent = fetch_comment(&database, path.id, path.comment_id).await?;
+
+	if parent.parent_id.is_some() {
+		return Err(Error::NestedReply(parent.id).into());
+	}
+
+	let reply = insert(&database, path.id, session.user.id, Some(parent.id), input).await?;
+
+	Ok(Json(reply))
+}
+
+/// Update comment
+/// Edits a comment. Only the author of the comment can edit it.
+#[route(tag = tag::COMMENT)]
+pub async fn update_comment(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::CommentInput>,
+	Json(input): Json<model::UpdateComment>,
+) -> Result<Json<model::Comment>, RouteError> {
+	fetch_author(&database, path.id, Some(session.user.id)).await?;
+
+	let comment = fetch_comment(&database, path.id, path.comment_id).await?;
+
+	if comment.user_id != session.user.id {
+		return Err(Error::Forbidden(comment.id).into());
+	}
+
+	let comment = sqlx::query_as!(
+		model::Comment,
+		r#"
+			UPDATE comment SET content = COALESCE($1, content)
+			WHERE id = $2
+			RETURNING
+				id, post_id, user_id, parent_id, content, created_at, updated_at,
+				(SELECT COUNT(*) FROM comment reply WHERE reply.parent_id = comment.id) AS "reply_count!"
+		"#,
+		input.content.value(),
+		comment.id,
+	)
+	.fetch_one(&database)
+	.await?;
+
+	Ok(Json(comment))
+}
+
+/// Delete comment
+/// Deletes a comment along with its replies. Comments can be deleted
+/// by their author, or by the author of the post.
+#[route(tag = tag::COMMENT)]
+pub async fn delete_comment(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::CommentInput>,
+) -> Result<(), RouteError> {
+	let author = fetch_author(&database, path.id, Some(session.user.id)).await?;
+	let comment = fetch_comment(&database, path.id, path.comment_id).await?;
+
+	if comment.user_id != session.user.id && author != session.user.id {
+		return Err(Error::Forbidden(comment.id).into());
+	}
+
+	sqlx::query!("DELETE FROM comment WHERE id = $1", comment.id)
+		.execute(&database)
+		.await?;
+
+	Ok(())
+}
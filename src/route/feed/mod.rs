@@ -0,0 +1,102 @@
+use aide::axum::{routing::get_with, ApiRouter};
+
+use crate::AppState;
+
+pub mod route;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new().api_route("/", get_with(get_feed, get_feed_docs))
+}
+
+#[cfg(test)]
+mod test {
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_feed(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let titles = |page: serde_json::Value| {
+			page["items"]
+				.as_array()
+				.unwrap()
+				.iter()
+				.map(|post| post["title"].as_str().unwrap().to_string())
+				.collect::<Vec<_>>()
+		};
+
+		// posts from before the follow are filled in as well
+		other
+			.post("/posts")
+			.json(&json!({ "title": "before", "content": "" }))
+			.await;
+
+		app.post("/users/jane/follow").await.assert_status_ok();
+
+		for (title, status, visibility) in [
+			("draft", "draft", "public"),
+			("after", "published", "public"),
+			("unlisted", "published", "unlisted"),
+			("followers", "published", "followers"),
+			("private", "published", "private"),
+		] {
+			other
+				.post("/posts")
+				.json(&json!({
+					"title": title,
+					"content": "",
+					"status": status,
+					"visibility": visibility,
+				}))
+				.await
+				.assert_status_ok();
+		}
+
+		let page = app.get("/feed").await.json::<serde_json::Value>();
+
+		assert_eq!(titles(page), ["followers", "after", "before"]);
+
+		let page = app
+			.get("/feed")
+			.add_query_param("size", 2)
+			.await
+			.json::<serde_json::Value>();
+		let cursor = page["next_cursor"].as_str().unwrap().to_string();
+		let page = app
+			.get("/feed")
+			.add_query_param("cursor", cursor)
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(titles(page), ["before"]);
+
+		// your own posts are not in your feed
+		app.post("/posts")
+			.json(&json!({ "title": "own", "content": "" }))
+			.await;
+
+		let page = other.get("/feed").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 0);
+
+		app.delete("/users/jane/follow").await.assert_status_ok();
+
+		let page = app.get("/feed").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 0);
+	}
+}
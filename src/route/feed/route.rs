@@ -0,0 +1,51 @@
+use axum::extract::State;
+use macros::route;
+
+use crate::{
+	extract::{Page, Pagination, Query, Session},
+	openapi::tag,
+	route::{
+		model::Sort,
+		post::{self, model, RouteError},
+	},
+	Database,
+};
+
+/// Get home feed
+/// Returns a paginated response of the posts of the users you follow, most recently published first.
+/// Posts that are unlisted or hidden from you are left out.
+#[route(tag = tag::FEED)]
+pub async fn get_feed(
+	State(database): State<Database>,
+	session: Session,
+	Query(render): Query<model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Post>, RouteError> {
+	let viewer = session.user.id;
+	let mut posts = pagination
+		.fetch(
+			&database,
+			model::Post::SELECT,
+			Sort::descending("published_at"),
+			|query| {
+				// The id and publication time come from the feed, so its index is used for the order
+				query
+					.push(" FROM (SELECT feed_entry.post_id AS id, post.user_id, title, slug, content, status,")
+					.push(" visibility, hidden, feed_entry.published_at, revision, comment_count, reactions, created_at,")
+					.push(" updated_at FROM feed_entry JOIN post ON post.id = feed_entry.post_id")
+					.push(" WHERE feed_entry.user_id = ")
+					.push_bind(viewer)
+					.push(") post WHERE ");
+				model::push_listed(query, Some(viewer));
+			},
+		)
+		.await?;
+
+	post::route::set_viewer_state(&database, Some(viewer), &mut posts.items).await?;
+
+	if render.html() {
+		post::route::render_html(&database, &mut posts.items).await?;
+	}
+
+	Ok(posts)
+}
@@ -1,4 +1,4 @@
-pub use crate::route::model::{IdInput, Paginate};
+pub use crate::route::model::{IdInput, Keyset, SortKey, UpdatedSinceInput};
 
 use schemars::JsonSchema;
 use serde::Serialize;
@@ -6,7 +6,7 @@ use uuid::Uuid;
 
 /// A single API key, owned by a user and used to perform automated
 /// actions on their behalf.
-#[derive(Debug, Serialize, JsonSchema)]
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
 pub struct Key {
 	/// The API key.
 	#[serde(skip_deserializing)]
@@ -19,4 +19,17 @@ pub struct Key {
 	/// The creation time of the key.
 	#[serde(skip_deserializing)]
 	pub created_at: chrono::DateTime<chrono::Utc>,
+	/// The last time the key was changed.
+	#[serde(skip_deserializing)]
+	pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Keyset for Key {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.created_at)
+	}
 }
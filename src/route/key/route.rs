@@ -2,8 +2,9 @@ use axum::extract::State;
 use macros::route;
 
 use crate::{
-	extract::{Json, Path, Query, Session},
+	extract::{Json, Page, Pagination, Path, Query, Session},
 	openapi::tag,
+	route::model::Sort,
 	AppState,
 };
 
@@ -15,23 +16,19 @@ use super::{model, Error, RouteError};
 pub async fn list_keys(
 	State(state): State<AppState>,
 	session: Session,
-	Query(paginate): Query<model::Paginate>,
-) -> Result<Json<Vec<model::Key>>, RouteError> {
-	let keys = sqlx::query_as!(
-		model::Key,
-		r#"
-			SELECT * FROM api_key WHERE user_id = $1
-			ORDER BY created_at DESC
-			LIMIT $2 OFFSET $3
-		"#,
-		session.user.id,
-		paginate.limit(),
-		paginate.offset()
-	)
-	.fetch_all(&state.database)
-	.await?;
+	Query(since): Query<model::UpdatedSinceInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Key>, RouteError> {
+	let keys = pagination
+		.fetch(&state.database, "SELECT *", Sort::NEWEST, |query| {
+			query
+				.push(" FROM api_key WHERE user_id = ")
+				.push_bind(session.user.id);
+			since.push_filter(query);
+		})
+		.await?;
 
-	Ok(Json(keys))
+	Ok(keys)
 }
 
 /// Create API key
@@ -45,7 +42,7 @@ pub async fn create_key(
 		model::Key,
 		r#"
 			INSERT INTO api_key (id, user_id) VALUES (DEFAULT, $1)
-			RETURNING id, user_id, created_at
+			RETURNING id, user_id, created_at, updated_at
 		"#,
 		session.user.id
 	)
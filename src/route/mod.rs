@@ -1,4 +1,11 @@
+pub mod attachment;
 pub mod auth;
+pub mod bookmark;
+pub mod comment;
+pub mod feed;
 pub mod key;
 pub mod model;
+pub mod moderation;
 pub mod post;
+pub mod tag;
+pub mod user;
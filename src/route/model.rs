@@ -1,5 +1,8 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -15,9 +18,14 @@ fn ten() -> i64 {
 	10
 }
 
+#[inline]
+fn yes() -> bool {
+	true
+}
+
 #[derive(Deserialize, Validate, JsonSchema)]
 pub struct Paginate {
-	/// The page number to return (1-indexed).
+	/// The page number to return (1-indexed). Ignored when a cursor is provided.
 	#[validate(range(min = 1, max = 100))]
 	#[serde(default = "one")]
 	pub page: i64,
@@ -25,6 +33,13 @@ pub struct Paginate {
 	#[validate(range(min = 1, max = 100))]
 	#[serde(default = "ten")]
 	pub size: i64,
+	/// An opaque cursor taken from the `next_cursor` or `prev_cursor` of a previous page.
+	/// When present, the items directly after (or before) it are returned.
+	pub cursor: Option<Cursor>,
+	/// Whether to count the total number of items. Set this to `false` to skip
+	/// the extra query when the total is not needed.
+	#[serde(default = "yes")]
+	pub count: bool,
 }
 
 impl Paginate {
@@ -35,6 +50,218 @@ impl Paginate {
 	pub fn limit(&self) -> i64 {
 		self.size
 	}
+
+	/// Pushes the ordering and bounds of the page onto a query that
+	/// selects rows implementing [`Keyset`].
+	///
+	/// The query must already end in a `WHERE` clause, as the cursor
+	/// condition is appended with `AND`. One more row than the page size
+	/// is fetched so that [`Paginate::cursors`] can tell if there are more.
+	pub fn push_bounds(&self, query: &mut QueryBuilder<'_, Postgres>, sort: Sort) {
+		let column = sort.column;
+		let descending = match &self.cursor {
+			Some(cursor) => sort.descending ^ (cursor.direction == Direction::Prev),
+			None => sort.descending,
+		};
+
+		let (comparison, order) = if descending {
+			("<", "DESC")
+		} else {
+			(">", "ASC")
+		};
+
+		if let Some(cursor) = &self.cursor {
+			query.push(format_args!(" AND ({column}, id) {comparison} ("));
+			cursor.key.clone().push_bind(query);
+			query.push(", ").push_bind(cursor.id).push(")");
+		}
+
+		query
+			.push(format_args!(
+				" ORDER BY {column} {order}, id {order} LIMIT "
+			))
+			.push_bind(self.limit() + 1);
+
+		if self.cursor.is_none() {
+			query.push(" OFFSET ").push_bind(self.offset());
+		}
+	}
+
+	/// Trims rows fetched with [`Paginate::push_bounds`] down to the page size,
+	/// restoring the requested order, and returns the cursors to the next and
+	/// previous pages, if they exist.
+	pub fn cursors<T: Keyset>(
+		&self,
+		rows: &mut Vec<T>,
+		sort: Sort,
+	) -> (Option<Cursor>, Option<Cursor>) {
+		let size = usize::try_from(self.size).unwrap_or_default();
+		let has_more = rows.len() > size;
+
+		rows.truncate(size);
+
+		let (has_next, has_prev) = match self.cursor.as_ref().map(|cursor| cursor.direction) {
+			None => (has_more, self.page > 1),
+			Some(Direction::Next) => (has_more, true),
+			Some(Direction::Prev) => {
+				rows.reverse();
+				(true, has_more)
+			}
+		};
+
+		let next = rows
+			.last()
+			.filter(|_| has_next)
+			.map(|row| Cursor::new(row, sort, Direction::Next));
+		let prev = rows
+			.first()
+			.filter(|_| has_prev)
+			.map(|row| Cursor::new(row, sort, Direction::Prev));
+
+		(next, prev)
+	}
+}
+
+/// The order of a paginated list: a column to sort by, and then
+/// the unique id of each row as a tie-breaker.
+///
+/// The column is pushed into the query verbatim, so it must never
+/// come from user input directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sort {
+	pub column: &'static str,
+	pub descending: bool,
+}
+
+impl Sort {
+	/// Newest first, the default order of every list.
+	pub const NEWEST: Self = Self::descending("created_at");
+
+	pub const fn ascending(column: &'static str) -> Self {
+		Self {
+			column,
+			descending: false,
+		}
+	}
+
+	pub const fn descending(column: &'static str) -> Self {
+		Self {
+			column,
+			descending: true,
+		}
+	}
+}
+
+/// The value of the column that a list is sorted by, stored in a [`Cursor`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SortKey {
+	Timestamp(DateTime<Utc>),
+	Text(String),
+	Number(f64),
+}
+
+impl SortKey {
+	fn push_bind(self, query: &mut QueryBuilder<'_, Postgres>) {
+		match self {
+			Self::Timestamp(value) => query.push_bind(value),
+			Self::Text(value) => query.push_bind(value),
+			Self::Number(value) => query.push_bind(value),
+		};
+	}
+}
+
+/// A row that can be paginated with a [`Cursor`].
+pub trait Keyset {
+	/// Returns the unique id of the row.
+	fn id(&self) -> Uuid;
+	/// Returns the value of the column that the list is sorted by.
+	fn key(&self, column: &str) -> SortKey;
+}
+
+/// The direction to continue in from a [`Cursor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+	/// The items after the cursor.
+	Next,
+	/// The items before the cursor.
+	Prev,
+}
+
+/// An opaque position in a sorted list.
+///
+/// Cursors are (de)serialized as URL-safe base64 strings so that clients
+/// treat them as opaque values and pass them back verbatim.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Cursor {
+	/// The column that the list was sorted by when the cursor was created.
+	pub column: String,
+	/// Whether the list was sorted in descending order when the cursor was created.
+	pub descending: bool,
+	pub key: SortKey,
+	pub id: Uuid,
+	pub direction: Direction,
+}
+
+impl Cursor {
+	pub fn new(row: &impl Keyset, sort: Sort, direction: Direction) -> Self {
+		Self {
+			column: sort.column.into(),
+			descending: sort.descending,
+			key: row.key(sort.column),
+			id: row.id(),
+			direction,
+		}
+	}
+
+	pub fn encode(&self) -> String {
+		let raw = (
+			&self.column,
+			self.descending,
+			&self.key,
+			self.id,
+			self.direction,
+		);
+
+		URL_SAFE_NO_PAD.encode(serde_json::to_vec(&raw).unwrap_or_default())
+	}
+
+	pub fn decode(cursor: &str) -> Option<Self> {
+		let raw = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+		let (column, descending, key, id, direction) = serde_json::from_slice(&raw).ok()?;
+
+		Some(Self {
+			column,
+			descending,
+			key,
+			id,
+			direction,
+		})
+	}
+}
+
+impl From<Cursor> for String {
+	fn from(cursor: Cursor) -> Self {
+		cursor.encode()
+	}
+}
+
+impl TryFrom<String> for Cursor {
+	type Error = &'static str;
+
+	fn try_from(cursor: String) -> Result<Self, Self::Error> {
+		Self::decode(&cursor).ok_or("invalid cursor")
+	}
+}
+
+impl JsonSchema for Cursor {
+	fn schema_name() -> String {
+		"Cursor".into()
+	}
+
+	fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+		String::json_schema(gen)
+	}
 }
 
 #[derive(Deserialize, Validate, JsonSchema)]
@@ -42,11 +269,36 @@ pub struct IdInput {
 	pub id: Uuid,
 }
 
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct UpdatedSinceInput {
+	/// Only return items changed (or created) strictly after this time. Clients can
+	/// pass the time of their previous request to only fetch what changed since.
+	pub updated_since: Option<DateTime<Utc>>,
+}
+
+impl UpdatedSinceInput {
+	/// Pushes the filter onto a query that already ends in a `WHERE` clause.
+	pub fn push_filter(&self, query: &mut QueryBuilder<'_, Postgres>) {
+		if let Some(since) = self.updated_since {
+			query.push(" AND updated_at > ").push_bind(since);
+		}
+	}
+}
+
 #[cfg(test)]
 mod test {
+	use validator::Validate;
+
+	use super::{Cursor, Direction, Paginate, SortKey};
+
 	#[test]
 	fn test_paginate_offset() {
-		let mut paginate = super::Paginate { page: 1, size: 10 };
+		let mut paginate = Paginate {
+			page: 1,
+			size: 10,
+			cursor: None,
+			count: true,
+		};
 
 		assert_eq!(paginate.offset(), 0);
 
@@ -63,10 +315,45 @@ mod test {
 		assert_eq!(paginate.offset(), 10);
 	}
 
+	#[test]
+	fn test_paginate_validate() {
+		let paginate = |page| Paginate {
+			page,
+			size: 10,
+			cursor: None,
+			count: true,
+		};
+
+		assert!(paginate(100).validate().is_ok());
+		assert!(paginate(101).validate().is_err());
+		assert!(paginate(i64::MAX).validate().is_err());
+	}
+
 	#[test]
 	fn test_paginate_limit() {
-		let paginate = super::Paginate { page: 1, size: 10 };
+		let paginate = Paginate {
+			page: 1,
+			size: 10,
+			cursor: None,
+			count: true,
+		};
 
 		assert_eq!(paginate.limit(), 10);
 	}
+
+	#[test]
+	fn test_cursor_round_trip() {
+		let cursor = Cursor {
+			column: "created_at".into(),
+			descending: true,
+			key: SortKey::Timestamp(
+				chrono::DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap(),
+			),
+			id: uuid::Uuid::new_v4(),
+			direction: Direction::Prev,
+		};
+
+		assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+		assert_eq!(Cursor::decode("not a cursor"), None);
+	}
 }
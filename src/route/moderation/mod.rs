@@ -0,0 +1,209 @@
+use aide::axum::{
+	routing::{get_with, post_with},
+	ApiRouter,
+};
+
+use crate::AppState;
+
+pub mod model;
+pub mod route;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new()
+		.api_route("/posts", get_with(get_queue, get_queue_docs))
+		.api_route(
+			"/posts/:id/reports",
+			get_with(get_reports, get_reports_docs),
+		)
+		.api_route(
+			"/posts/:id/resolve",
+			post_with(resolve_reports, resolve_reports_docs),
+		)
+		.api_route(
+			"/posts/:id/dismiss",
+			post_with(dismiss_reports, dismiss_reports_docs),
+		)
+		.api_route("/posts/:id/hide", post_with(hide_post, hide_post_docs))
+}
+
+#[cfg(test)]
+mod test {
+	use axum::http::StatusCode;
+
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_moderation(pool: Database) {
+		let anonymous = app(pool.clone());
+		let admin = app(pool.clone());
+		let reporters = (0..5).map(|_| app(pool.clone())).collect::<Vec<_>>();
+		let app = app(pool.clone());
+
+		let names = reporters
+			.iter()
+			.enumerate()
+			.map(|(i, reporter)| (reporter, format!("reporter{i}")));
+
+		for (server, name) in [(&app, "john".into()), (&admin, "admin".into())]
+			.into_iter()
+			.chain(names)
+		{
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await
+				.assert_status_ok();
+		}
+
+		sqlx::query!(r#"UPDATE "user" SET is_admin = TRUE WHERE username = 'admin'"#)
+			.execute(&pool)
+			.await
+			.unwrap();
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "Buy now", "content": "cheap" }))
+			.await
+			.json::<serde_json::Value>();
+		let id = post["id"].as_str().unwrap();
+		let path = format!("/posts/{id}");
+
+		let response = app
+			.post(&format!("{path}/report"))
+			.json(&json!({ "reason": "spam" }))
+			.await;
+
+		response.assert_status(StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"cannot_report_own_post"
+		);
+
+		// reports are deduplicated per reporter
+		for reason in ["other", "spam"] {
+			let report = reporters[0]
+				.post(&format!("{path}/report"))
+				.json(&json!({ "reason": reason, "details": "an advert" }))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(report["reason"], reason);
+			assert_eq!(report["status"], "open");
+		}
+
+		let response = reporters[0].get("/moderation/posts").await;
+
+		response.assert_status(StatusCode::FORBIDDEN);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"admin_required"
+		);
+
+		for reporter in &reporters[1..4] {
+			reporter
+				.post(&format!("{path}/report"))
+				.json(&json!({ "reason": "harassment" }))
+				.await
+				.assert_status_ok();
+		}
+
+		anonymous.get(&path).await.assert_status_ok();
+
+		// the fifth distinct report hides the post
+		reporters[4]
+			.post(&format!("{path}/report"))
+			.json(&json!({ "reason": "spam" }))
+			.await
+			.assert_status_ok();
+
+		let response = anonymous.get(&path).await;
+
+		response.assert_status_not_found();
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"post_not_found"
+		);
+		reporters[0].get(&path).await.assert_status_not_found();
+		anonymous
+			.get(&format!("{path}/comments"))
+			.await
+			.assert_status_not_found();
+		assert_eq!(
+			anonymous.get("/posts").await.json::<serde_json::Value>()["total"],
+			0
+		);
+
+		let post = app.get(&path).await.json::<serde_json::Value>();
+
+		assert_eq!(post["hidden"], true);
+		admin.get(&path).await.assert_status_ok();
+
+		let queue = admin
+			.get("/moderation/posts")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(queue["total"], 1);
+		assert_eq!(queue["items"][0]["id"], id);
+		assert_eq!(queue["items"][0]["report_count"], 5);
+		assert_eq!(queue["items"][0]["reasons"], json!(["spam", "harassment"]));
+
+		let reports = admin
+			.get(&format!("/moderation/posts/{id}/reports"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(reports["total"], 5);
+
+		let moderation = admin
+			.post(&format!("/moderation/posts/{id}/dismiss"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(moderation["hidden"], false);
+		assert_eq!(moderation["closed_reports"], 5);
+		anonymous.get(&path).await.assert_status_ok();
+
+		let queue = admin
+			.get("/moderation/posts")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(queue["total"], 0);
+
+		// dismissed reports do not count towards hiding the post again
+		let report = reporters[0]
+			.post(&format!("{path}/report"))
+			.json(&json!({ "reason": "spam" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(report["status"], "dismissed");
+		assert_eq!(
+			admin
+				.get("/moderation/posts")
+				.await
+				.json::<serde_json::Value>()["total"],
+			0
+		);
+
+		let moderation = admin
+			.post(&format!("/moderation/posts/{id}/hide"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(moderation["hidden"], true);
+		anonymous.get(&path).await.assert_status_not_found();
+
+		admin
+			.post("/moderation/posts/00000000-0000-0000-0000-000000000000/resolve")
+			.await
+			.assert_status_not_found();
+	}
+}
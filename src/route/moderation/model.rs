@@ -0,0 +1,52 @@
+pub use crate::route::model::{IdInput, Keyset, SortKey};
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::route::post::model::{Post, ReportReason};
+
+/// A post with open reports, waiting for a moderator.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct ReportedPost {
+	#[serde(flatten)]
+	#[sqlx(flatten)]
+	pub post: Post,
+	/// The number of open reports of the post.
+	pub report_count: i64,
+	/// The reasons that the post was reported for.
+	pub reasons: Vec<ReportReason>,
+	/// When the oldest open report of the post was created.
+	pub reported_at: DateTime<Utc>,
+}
+
+impl ReportedPost {
+	/// The columns selected for a [`ReportedPost`], from a subquery
+	/// that exposes the open reports next to each post.
+	pub const SELECT: &'static str =
+		"SELECT id, user_id, title, slug, content, status, visibility, \
+		hidden, published_at, revision, comment_count, reactions, created_at, updated_at, post_tags(id) AS tags, \
+		report_count, reasons, reported_at";
+}
+
+impl Keyset for ReportedPost {
+	fn id(&self) -> Uuid {
+		self.post.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.reported_at)
+	}
+}
+
+/// The outcome of moderating a reported post.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Moderation {
+	/// The post that was moderated.
+	pub post_id: Uuid,
+	/// Whether the post is now hidden.
+	pub hidden: bool,
+	/// The number of open reports that were closed.
+	pub closed_reports: u64,
+}
@@ -0,0 +1,180 @@
+use axum::extract::State;
+use macros::route;
+use uuid::Uuid;
+
+use crate::{
+	extract::{AdminSession, Json, Page, Pagination, Path},
+	openapi::tag,
+	route::{
+		model::Sort,
+		post::{self, Error, RouteError},
+	},
+	Database,
+};
+
+use super::model;
+
+/// Closes the open reports of a post, and hides or shows it if `hidden` is given.
+async fn moderate(
+	database: &Database,
+	moderator: Uuid,
+	post_id: Uuid,
+	status: post::model::ReportStatus,
+	hidden: Option<bool>,
+) -> Result<model::Moderation, RouteError> {
+	let mut tx = database.begin().await?;
+	let current = sqlx::query_scalar!("SELECT hidden FROM post WHERE id = $1 FOR UPDATE", post_id)
+		.fetch_optional(&mut *tx)
+		.await?
+		.ok_or(Error::UnknownPost(post_id))?;
+
+	// Only changed when needed, so the post keeps its `updated_at` otherwise
+	let hidden = match hidden {
+		Some(hidden) if hidden != current => {
+			sqlx::query!("UPDATE post SET hidden = $2 WHERE id = $1", post_id, hidden)
+				.execute(&mut *tx)
+				.await?;
+			hidden
+		}
+		_ => current,
+	};
+
+	let closed = sqlx::query!(
+		r#"
+			UPDATE report SET status = $2, moderator_id = $3, resolved_at = now()
+			WHERE post_id = $1 AND status = 'open'
+		"#,
+		post_id,
+		status as post::model::ReportStatus,
+		moderator,
+	)
+	.execute(&mut *tx)
+	.await?;
+
+	tx.commit().await?;
+
+	Ok(model::Moderation {
+		post_id,
+		hidden,
+		closed_reports: closed.rows_affected(),
+	})
+}
+
+/// Get moderation queue
+/// Returns a paginated response of the posts with open reports, the longest waiting first.
+/// Posts that were hidden after being reported by several users are included.
+#[route(tag = tag::MODERATION)]
+pub async fn get_queue(
+	State(database): State<Database>,
+	_admin: AdminSession,
+	pagination: Pagination,
+) -> Result<Page<model::ReportedPost>, RouteError> {
+	let posts = pagination
+		.fetch(
+			&database,
+			model::ReportedPost::SELECT,
+			Sort::ascending("reported_at"),
+			|query| {
+				query
+					.push(" FROM (SELECT post.*, report_count, reasons, reported_at FROM post")
+					.push(" JOIN (SELECT post_id, COUNT(*) AS report_count, array_agg(DISTINCT reason) AS reasons,")
+					.push(" MIN(created_at) AS reported_at FROM report WHERE status = 'open' GROUP BY post_id)")
+					.push(" queue ON queue.post_id = post.id) post WHERE TRUE");
+			},
+		)
+		.await?;
+
+	Ok(posts)
+}
+
+/// Get post reports
+/// Returns a paginated response of every report of a post, including closed ones, newest first.
+#[route(tag = tag::MODERATION)]
+pub async fn get_reports(
+	State(database): State<Database>,
+	_admin: AdminSession,
+	Path(path): Path<model::IdInput>,
+	pagination: Pagination,
+) -> Result<Page<post::model::Report>, RouteError> {
+	sqlx::query_scalar!("SELECT id FROM post WHERE id = $1", path.id)
+		.fetch_optional(&database)
+		.await?
+		.ok_or(Error::UnknownPost(path.id))?;
+
+	let reports = pagination
+		.fetch(
+			&database,
+			post::model::Report::SELECT,
+			Sort::NEWEST,
+			|query| {
+				query
+					.push(" FROM report WHERE post_id = ")
+					.push_bind(path.id);
+			},
+		)
+		.await?;
+
+	Ok(reports)
+}
+
+/// Resolve reports
+/// Closes the open reports of a post as resolved, after acting on them some other way.
+/// The post stays hidden if it is hidden.
+#[route(tag = tag::MODERATION)]
+pub async fn resolve_reports(
+	State(database): State<Database>,
+	AdminSession(session): AdminSession,
+	Path(path): Path<model::IdInput>,
+) -> Result<Json<model::Moderation>, RouteError> {
+	let moderation = moderate(
+		&database,
+		session.user.id,
+		path.id,
+		post::model::ReportStatus::Resolved,
+		None,
+	)
+	.await?;
+
+	Ok(Json(moderation))
+}
+
+/// Dismiss reports
+/// Closes the open reports of a post as dismissed, since it does not break the rules,
+/// and shows the post again if it was hidden.
+#[route(tag = tag::MODERATION)]
+pub async fn dismiss_reports(
+	State(database): State<Database>,
+	AdminSession(session): AdminSession,
+	Path(path): Path<model::IdInput>,
+) -> Result<Json<model::Moderation>, RouteError> {
+	let moderation = moderate(
+		&database,
+		session.user.id,
+		path.id,
+		post::model::ReportStatus::Dismissed,
+		Some(false),
+	)
+	.await?;
+
+	Ok(Json(moderation))
+}
+
+/// Hide post
+/// Hides a post from everyone except its author and admins, and closes its open reports as resolved.
+#[route(tag = tag::MODERATION)]
+pub async fn hide_post(
+	State(database): State<Database>,
+	AdminSession(session): AdminSession,
+	Path(path): Path<model::IdInput>,
+) -> Result<Json<model::Moderation>, RouteError> {
+	let moderation = moderate(
+		&database,
+		session.user.id,
+		path.id,
+		post::model::ReportStatus::Resolved,
+		Some(true),
+	)
+	.await?;
+
+	Ok(Json(moderation))
+}
@@ -0,0 +1,302 @@
+//! Filters that posts go through before they are created or updated.
+//!
+//! A [`Chain`] runs its filters in order and rejects the post with the first
+//! error, such as [`Error::BannedWord`]. The chain used by the app is loaded
+//! from the TOML file in the `FILTERS` environment variable, where each rule
+//! has its own (optional) section:
+//!
+//! ```toml
+//! [banned_words]
+//! words = ["viagra"]
+//!
+//! [link_limit]
+//! max = 5
+//!
+//! [duplicate_content]
+//! hours = 24
+//!
+//! [rate_limit]
+//! posts_per_hour = 10
+//! ```
+
+use std::{collections::HashSet, path::Path};
+
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use super::{Error, RouteError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+	#[error("io error: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("toml error: {0}")]
+	Toml(#[from] toml::de::Error),
+}
+
+/// The title and content of a post that is about to be saved.
+pub struct Draft<'a> {
+	pub user_id: Uuid,
+	/// The post being updated, or `None` for a new post.
+	pub post_id: Option<Uuid>,
+	pub title: &'a str,
+	pub content: &'a str,
+}
+
+/// A rule that a post must follow to be saved.
+#[axum::async_trait]
+pub trait Filter: Send + Sync {
+	/// Checks the draft, returning the error to reject it with if it breaks the rule.
+	/// The connection is the transaction that the post is saved in.
+	async fn check(&self, conn: &mut PgConnection, draft: &Draft<'_>) -> Result<(), RouteError>;
+}
+
+/// The rules of a [`Chain`], as written in its config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	pub banned_words: Option<BannedWords>,
+	pub link_limit: Option<LinkLimit>,
+	pub duplicate_content: Option<DuplicateContent>,
+	pub rate_limit: Option<RateLimit>,
+}
+
+/// A list of filters, run in order.
+#[derive(Default)]
+pub struct Chain(Vec<Box<dyn Filter>>);
+
+impl Chain {
+	/// Adds a filter to the end of the chain.
+	#[must_use]
+	pub fn with(mut self, filter: impl Filter + 'static) -> Self {
+		self.0.push(Box::new(filter));
+		self
+	}
+
+	/// Builds a chain with the rules in `config`, cheapest first.
+	pub fn from_config(config: Config) -> Self {
+		let filters: [Option<Box<dyn Filter>>; 4] = [
+			config.banned_words.map(|f| Box::new(f.normalized()) as _),
+			config.link_limit.map(|f| Box::new(f) as _),
+			config.rate_limit.map(|f| Box::new(f) as _),
+			config.duplicate_content.map(|f| Box::new(f) as _),
+		];
+
+		Self(filters.into_iter().flatten().collect())
+	}
+
+	/// Reads the config of a chain from a TOML file.
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+		let config = std::fs::read_to_string(path)?;
+
+		Ok(Self::from_config(toml::from_str(&config)?))
+	}
+
+	/// Runs every filter on the draft, stopping at the first one that rejects it.
+	pub async fn check(
+		&self,
+		conn: &mut PgConnection,
+		draft: &Draft<'_>,
+	) -> Result<(), RouteError> {
+		for filter in &self.0 {
+			filter.check(&mut *conn, draft).await?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Rejects posts that contain any of the words, ignoring case. An entry can also be
+/// a phrase of several words, which matches them in order, separated by anything
+/// other than letters and digits.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BannedWords {
+	pub words: HashSet<String>,
+}
+
+impl BannedWords {
+	fn normalized(self) -> Self {
+		Self {
+			words: self.words.iter().map(|word| normalize(word)).collect(),
+		}
+	}
+}
+
+/// Lowercases the words of a text and joins them with single spaces.
+fn normalize(text: &str) -> String {
+	text.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(str::to_lowercase)
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+#[axum::async_trait]
+impl Filter for BannedWords {
+	async fn check(&self, _conn: &mut PgConnection, draft: &Draft<'_>) -> Result<(), RouteError> {
+		// Padded with spaces, so that every word is matched as a whole
+		let texts = [draft.title, draft.content].map(|text| format!(" {} ", normalize(text)));
+		let banned = self.words.iter().find(|word| {
+			!word.is_empty() && texts.iter().any(|text| text.contains(&format!(" {word} ")))
+		});
+
+		match banned {
+			Some(word) => Err(Error::BannedWord(word.clone()).into()),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Rejects posts with more than `max` links in their title and content.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkLimit {
+	pub max: usize,
+}
+
+#[axum::async_trait]
+impl Filter for LinkLimit {
+	async fn check(&self, _conn: &mut PgConnection, draft: &Draft<'_>) -> Result<(), RouteError> {
+		let count = [draft.title, draft.content]
+			.into_iter()
+			.map(str::to_ascii_lowercase)
+			.map(|text| text.matches("http://").count() + text.matches("https://").count())
+			.sum();
+
+		if count > self.max {
+			return Err(Error::TooManyLinks {
+				max: self.max,
+				count,
+			}
+			.into());
+		}
+
+		Ok(())
+	}
+}
+
+/// Rejects posts with the same content as another post by the same user
+/// created in the last `hours`, ignoring case and whitespace.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DuplicateContent {
+	pub hours: i32,
+}
+
+#[axum::async_trait]
+impl Filter for DuplicateContent {
+	async fn check(&self, conn: &mut PgConnection, draft: &Draft<'_>) -> Result<(), RouteError> {
+		// Posts without content, such as drafts that only have a title, are never duplicates
+		if draft.content.trim().is_empty() {
+			return Ok(());
+		}
+
+		let duplicate = sqlx::query_scalar!(
+			r#"
+				SELECT id FROM post
+				WHERE user_id = $1
+					AND id IS DISTINCT FROM $2
+					AND created_at > now() - make_interval(hours => $3)
+					AND lower(btrim(regexp_replace(content, '\s+', ' ', 'g')))
+						= lower(btrim(regexp_replace($4, '\s+', ' ', 'g')))
+				LIMIT 1
+			"#,
+			draft.user_id,
+			draft.post_id,
+			self.hours,
+			draft.content,
+		)
+		.fetch_optional(&mut *conn)
+		.await?;
+
+		match duplicate {
+			Some(id) => Err(Error::DuplicateContent(id).into()),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Rejects new posts once a user has created `posts_per_hour` posts in the last hour,
+/// including posts that were deleted since. Updates to existing posts are not limited.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+	pub posts_per_hour: i64,
+}
+
+#[axum::async_trait]
+impl Filter for RateLimit {
+	async fn check(&self, conn: &mut PgConnection, draft: &Draft<'_>) -> Result<(), RouteError> {
+		if draft.post_id.is_some() {
+			return Ok(());
+		}
+
+		// Posts of the same user are created one at a time, so they cannot exceed the limit together
+		sqlx::query!(
+			r#"SELECT id FROM "user" WHERE id = $1 FOR UPDATE"#,
+			draft.user_id
+		)
+		.fetch_one(&mut *conn)
+		.await?;
+
+		let recent = sqlx::query!(
+			r#"
+				SELECT COUNT(*) AS "count!", MIN(created_at) AS oldest FROM post_creation
+				WHERE user_id = $1 AND created_at > now() - interval '1 hour'
+			"#,
+			draft.user_id,
+		)
+		.fetch_one(&mut *conn)
+		.await?;
+
+		if recent.count < self.posts_per_hour {
+			return Ok(());
+		}
+
+		// Another post can be created once the oldest one in the window is an hour old
+		let retry_after = recent.oldest.map_or(Duration::zero(), |oldest| {
+			oldest + Duration::hours(1) - Utc::now()
+		});
+
+		Err(Error::PostRateLimited {
+			max: self.posts_per_hour,
+			retry_after: retry_after.num_seconds().max(1).unsigned_abs(),
+		}
+		.into())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_config() {
+		let config = toml::from_str::<Config>(
+			r#"
+				[banned_words]
+				words = ["Viagra"]
+
+				[link_limit]
+				max = 2
+			"#,
+		)
+		.unwrap();
+
+		assert!(config.duplicate_content.is_none());
+		assert_eq!(config.link_limit.unwrap().max, 2);
+		assert!(config
+			.banned_words
+			.unwrap()
+			.normalized()
+			.words
+			.contains("viagra"));
+
+		assert!(toml::from_str::<Config>("[spam]\nmax = 1").is_err());
+		assert_eq!(normalize("  Free--MONEY now! "), "free money now");
+		assert!(toml::from_str::<Config>(include_str!("../../../filters.toml")).is_ok());
+	}
+}
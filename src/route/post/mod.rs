@@ -1,9 +1,16 @@
-use aide::axum::{routing::get_with, ApiRouter};
-use axum::http::StatusCode;
+use aide::axum::{
+	routing::{get_with, post_with, put_with},
+	ApiRouter,
+};
+use axum::{
+	extract::DefaultBodyLimit,
+	http::{header, HeaderMap, HeaderValue, StatusCode},
+};
 use uuid::Uuid;
 
 use crate::{error, AppState};
 
+pub mod filter;
 pub mod model;
 pub mod route;
 
@@ -11,6 +18,22 @@ pub mod route;
 pub enum Error {
 	#[error("post_not_found")]
 	UnknownPost(Uuid),
+	#[error("post_not_found")]
+	UnknownSlug(String),
+	#[error("revision_not_found")]
+	UnknownRevision(i32),
+	#[error("invalid_publish_time")]
+	InvalidPublishTime,
+	#[error("cannot_report_own_post")]
+	ReportOwnPost,
+	#[error("banned_word")]
+	BannedWord(String),
+	#[error("too_many_links")]
+	TooManyLinks { max: usize, count: usize },
+	#[error("duplicate_content")]
+	DuplicateContent(Uuid),
+	#[error("post_rate_limited")]
+	PostRateLimited { max: i64, retry_after: u64 },
 }
 
 pub type RouteError = error::RouteError<Error>;
@@ -24,29 +47,1622 @@ pub fn routes() -> ApiRouter<AppState> {
 			get_with(get_posts, get_posts_docs).post_with(create_post, create_post_docs),
 		)
 		.api_route("/me", get_with(get_user_posts, get_user_posts_docs))
+		.api_route(
+			"/bulk",
+			post_with(bulk_update_posts, bulk_update_posts_docs)
+				.layer(DefaultBodyLimit::max(model::MAX_BULK_SIZE)),
+		)
+		.api_route("/search", get_with(search_posts, search_posts_docs))
+		.api_route("/feed.atom", get_with(get_atom_feed, get_atom_feed_docs))
+		.api_route("/feed.rss", get_with(get_rss_feed, get_rss_feed_docs))
 		.api_route(
 			"/:id",
 			get_with(get_post, get_post_docs)
 				.put_with(update_post, update_post_docs)
+				.patch_with(patch_post, patch_post_docs)
 				.delete_with(delete_post, delete_post_docs),
 		)
+		.api_route(
+			"/:id/revisions",
+			get_with(get_revisions, get_revisions_docs),
+		)
+		.api_route(
+			"/:id/revisions/diff",
+			get_with(diff_revisions, diff_revisions_docs),
+		)
+		.api_route(
+			"/:id/revisions/:rev",
+			get_with(get_revision, get_revision_docs),
+		)
+		.api_route(
+			"/:id/revisions/:rev/restore",
+			post_with(restore_revision, restore_revision_docs),
+		)
+		.api_route(
+			"/:id/reactions/:kind",
+			put_with(add_reaction, add_reaction_docs)
+				.delete_with(remove_reaction, remove_reaction_docs),
+		)
+		.api_route(
+			"/:id/bookmark",
+			put_with(add_bookmark, add_bookmark_docs)
+				.delete_with(remove_bookmark, remove_bookmark_docs),
+		)
+		.api_route("/:id/report", post_with(report_post, report_post_docs))
 }
 
 impl error::ErrorShape for Error {
 	fn status(&self) -> StatusCode {
 		match self {
-			Self::UnknownPost(..) => StatusCode::NOT_FOUND,
+			Self::UnknownPost(..) | Self::UnknownSlug(..) | Self::UnknownRevision(..) => {
+				StatusCode::NOT_FOUND
+			}
+			Self::InvalidPublishTime | Self::ReportOwnPost => StatusCode::BAD_REQUEST,
+			Self::BannedWord(..) | Self::TooManyLinks { .. } | Self::DuplicateContent(..) => {
+				StatusCode::UNPROCESSABLE_ENTITY
+			}
+			Self::PostRateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
 		}
 	}
 
+	fn headers(&self) -> Option<HeaderMap> {
+		let Self::PostRateLimited { retry_after, .. } = self else {
+			return None;
+		};
+
+		let retry_after = HeaderValue::from(*retry_after);
+
+		Some([(header::RETRY_AFTER, retry_after)].into_iter().collect())
+	}
+
 	fn into_errors(self) -> Vec<error::Message<'static>> {
 		let message = match self {
-			Self::UnknownPost(..) => "The post you provided does not exist.",
+			Self::UnknownPost(..) | Self::UnknownSlug(..) => {
+				"The post you provided does not exist."
+			}
+			Self::UnknownRevision(..) => "The revision you provided does not exist.",
+			Self::InvalidPublishTime => "Scheduled posts must be published in the future.",
+			Self::ReportOwnPost => "You cannot report your own post.",
+			Self::BannedWord(..) => "The post contains a word that is not allowed.",
+			Self::TooManyLinks { .. } => "The post contains too many links.",
+			Self::DuplicateContent(..) => "You recently created a post with the same content.",
+			Self::PostRateLimited { .. } => "You are creating posts too quickly.",
 		};
 
 		let message = error::Message::new(self.to_string()).content(message);
-		let Self::UnknownPost(key) = self;
 
-		message.detail("key", key.to_string()).into_vec()
+		match self {
+			Self::UnknownPost(key) => message.detail("key", key.to_string()),
+			Self::UnknownSlug(slug) => message.detail("slug", slug),
+			Self::UnknownRevision(rev) => message.detail("rev", rev),
+			Self::InvalidPublishTime | Self::ReportOwnPost => message,
+			Self::BannedWord(word) => message.detail("rule", "banned_words").detail("word", word),
+			Self::TooManyLinks { max, count } => message
+				.detail("rule", "link_limit")
+				.detail("max", max)
+				.detail("count", count),
+			Self::DuplicateContent(key) => message
+				.detail("rule", "duplicate_content")
+				.detail("key", key.to_string()),
+			Self::PostRateLimited { max, retry_after } => message
+				.detail("rule", "rate_limit")
+				.detail("max", max)
+				.detail("retry_after", retry_after),
+		}
+		.into_vec()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::future::IntoFuture;
+
+	use axum::{
+		extract::DefaultBodyLimit,
+		http::{header, HeaderMap, HeaderValue, StatusCode},
+	};
+	use axum_test::{TestResponse, TestServer};
+
+	use crate::test::*;
+
+	/// Follows the link with the given relation from a paginated response.
+	async fn follow(app: &TestServer, response: &TestResponse, rel: &str) -> TestResponse {
+		let header = response.header("link");
+		let link = header
+			.to_str()
+			.unwrap()
+			.split(", ")
+			.find(|link| link.ends_with(&format!("rel=\"{rel}\"")))
+			.unwrap();
+
+		let (path, query) = link[1..link.find('>').unwrap()].split_once('?').unwrap();
+
+		app.get(path).add_raw_query_param(query).await
+	}
+
+	#[sqlx::test]
+	async fn test_cursor_pagination(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		for title in ["first", "second", "third"] {
+			app.post("/posts")
+				.json(&json!({ "title": title, "content": "" }))
+				.await;
+		}
+
+		let response = app.get("/posts").add_query_param("size", 2).await;
+		let page = response.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "third");
+		assert_eq!(page["items"][1]["title"], "second");
+		assert_eq!(page["page"], 1);
+		assert_eq!(page["total"], 3);
+		assert_eq!(page["has_more"], true);
+		assert!(page["prev_cursor"].is_null());
+		assert!(!response.header("link").to_str().unwrap().contains("prev"));
+
+		let response = follow(&app, &response, "next").await;
+		let page = response.json::<serde_json::Value>();
+
+		assert_eq!(page["items"].as_array().unwrap().len(), 1);
+		assert_eq!(page["items"][0]["title"], "first");
+		assert!(page["page"].is_null());
+		assert_eq!(page["has_more"], false);
+		assert!(!response.header("link").to_str().unwrap().contains("next"));
+
+		let page = follow(&app, &response, "prev")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "third");
+		assert_eq!(page["items"][1]["title"], "second");
+
+		let page = app
+			.get("/posts")
+			.add_query_param("page", 2)
+			.add_query_param("size", 2)
+			.add_query_param("count", false)
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "first");
+		assert_eq!(page["has_more"], false);
+		assert!(page.get("total").is_none());
+	}
+
+	#[sqlx::test]
+	async fn test_search(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		for (title, content) in [
+			("Cooking pasta", "A post about programming in Rust."),
+			("Programming in Rust", "A post about cooking pasta."),
+			("Gardening", "Nothing to see here."),
+			(
+				"Scripts",
+				"Tips <script>alert(1)</script> and <img src=x onerror=alert(1)>",
+			),
+		] {
+			app.post("/posts")
+				.json(&json!({ "title": title, "content": content }))
+				.await;
+		}
+
+		let page = app
+			.get("/posts/search")
+			.add_query_param("q", "program")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 2);
+		assert_eq!(page["items"][0]["title"], "Programming in Rust");
+		assert_eq!(page["items"][1]["title"], "Cooking pasta");
+		assert!(page["items"][1]["snippet"]
+			.as_str()
+			.unwrap()
+			.contains("<mark>programming</mark>"));
+
+		let page = app
+			.get("/posts/search")
+			.add_query_param("q", "\"cooking pasta\"")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 2);
+
+		let page = app
+			.get("/posts/search")
+			.add_query_param("q", "\"pasta cooking\"")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 0);
+
+		// the content is escaped, so only the `<mark>` tags are markup
+		let page = app
+			.get("/posts/search")
+			.add_query_param("q", "tips")
+			.await
+			.json::<serde_json::Value>();
+
+		let snippet = page["items"][0]["snippet"].as_str().unwrap();
+
+		assert!(snippet.starts_with("<mark>Tips</mark> &lt;script&gt;alert(1)&lt;/script&gt;"));
+		assert!(!snippet
+			.replace("<mark>", "")
+			.replace("</mark>", "")
+			.contains(['<', '>']));
+	}
+
+	#[sqlx::test]
+	async fn test_filter_and_sort(pool: Database) {
+		let app = app(pool);
+
+		for username in ["john", "jane"] {
+			app.post("/auth/register")
+				.json(&json!({
+					"email": format!("{username}@smith.com"),
+					"username": username,
+					"password": "hunter2hunter",
+				}))
+				.await;
+
+			for title in ["banana", "apple", "cherry"] {
+				app.post("/posts")
+					.json(&json!({ "title": format!("{username} {title}"), "content": "" }))
+					.await;
+			}
+		}
+
+		let page = app
+			.get("/posts")
+			.add_query_param("author", "john")
+			.add_query_param("sort", "title")
+			.add_query_param("size", 2)
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 3);
+		assert_eq!(page["items"][0]["title"], "john apple");
+		assert_eq!(page["items"][1]["title"], "john banana");
+
+		let page = app
+			.get("/posts")
+			.add_query_param("author", "john")
+			.add_query_param("sort", "title")
+			.add_query_param("cursor", page["next_cursor"].as_str().unwrap())
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "john cherry");
+		assert_eq!(page["has_more"], false);
+
+		let response = app.get("/posts").add_query_param("sort", "-title").await;
+
+		assert_eq!(response.status_code(), 400);
+
+		let response = app
+			.get("/posts")
+			.add_query_param("created_after", "2030-01-01T00:00:00Z")
+			.add_query_param("created_before", "2020-01-01T00:00:00Z")
+			.await;
+
+		assert_eq!(response.status_code(), 400);
+
+		let page = app
+			.get("/posts")
+			.add_query_param("sort", "created_at")
+			.add_query_param("created_before", "2100-01-01T00:00:00Z")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "john banana");
+		assert_eq!(page["items"][5]["title"], "jane cherry");
+
+		let page = app
+			.get("/posts")
+			.add_query_param("sort", "-created_at")
+			.add_query_param("size", 2)
+			.await
+			.json::<serde_json::Value>();
+
+		let response = app
+			.get("/posts")
+			.add_query_param("sort", "created_at")
+			.add_query_param("cursor", page["next_cursor"].as_str().unwrap())
+			.await;
+
+		assert_eq!(response.status_code(), 400);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"cursor_sort_mismatch"
+		);
+	}
+
+	#[sqlx::test]
+	async fn test_post_status(pool: Database) {
+		let app = app(pool.clone());
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let published = app
+			.post("/posts")
+			.json(&json!({ "title": "published", "content": "" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(published["status"], "published");
+		assert!(published["published_at"].is_string());
+
+		let draft = app
+			.post("/posts")
+			.json(&json!({ "title": "draft", "content": "", "status": "draft" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert!(draft["published_at"].is_null());
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "scheduled", "content": "", "status": "scheduled" }))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"invalid_publish_time"
+		);
+
+		let scheduled = app
+			.post("/posts")
+			.json(&json!({
+				"title": "scheduled",
+				"content": "",
+				"status": "scheduled",
+				"published_at": chrono::Utc::now() + chrono::Duration::hours(1),
+			}))
+			.await
+			.json::<serde_json::Value>();
+
+		let page = app
+			.get("/posts/me")
+			.add_query_param("status", "draft")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+		assert_eq!(page["items"][0]["title"], "draft");
+
+		let draft_path = format!("/posts/{}", draft["id"].as_str().unwrap());
+		let scheduled_path = format!("/posts/{}", scheduled["id"].as_str().unwrap());
+
+		app.get(&draft_path).await.assert_status_ok();
+		assert_eq!(
+			app.get("/posts").await.json::<serde_json::Value>()["total"],
+			3
+		);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "jane@smith.com",
+				"username": "jane",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let page = app.get("/posts").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+		assert_eq!(page["items"][0]["title"], "published");
+
+		let response = app.get(&draft_path).await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"post_not_found"
+		);
+
+		app.get(&scheduled_path).await.assert_status_not_found();
+
+		sqlx::query(
+			"UPDATE post SET published_at = now() - INTERVAL '1 minute' WHERE status = 'scheduled'",
+		)
+		.execute(&pool)
+		.await
+		.unwrap();
+
+		assert_eq!(
+			crate::scheduler::publish_scheduled_posts(&pool)
+				.await
+				.unwrap(),
+			1
+		);
+
+		let post = app.get(&scheduled_path).await.json::<serde_json::Value>();
+
+		assert_eq!(post["status"], "published");
+	}
+
+	#[sqlx::test]
+	async fn test_revisions(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "First draft", "content": "the quick brown fox" }))
+			.await
+			.json::<serde_json::Value>();
+		let path = format!("/posts/{}", post["id"].as_str().unwrap());
+
+		assert_eq!(post["revision"], 1);
+
+		app.put(&path)
+			.json(&json!({ "content": "the slow brown fox" }))
+			.await;
+
+		let post = app
+			.put(&path)
+			.json(&json!({ "title": "Final draft" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["revision"], 3);
+
+		// saving the same title and content doesn't create a revision
+		let post = app
+			.put(&path)
+			.json(&json!({ "title": "Final draft", "status": "published" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["status"], "published");
+		assert_eq!(post["revision"], 3);
+
+		let page = app
+			.get(&format!("{path}/revisions"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 3);
+		assert_eq!(page["items"][0]["number"], 3);
+		assert_eq!(page["items"][2]["content"], "the quick brown fox");
+
+		let diff = app
+			.get(&format!("{path}/revisions/diff"))
+			.add_query_param("from", 1)
+			.add_query_param("to", 3)
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(
+			diff["title"],
+			json!([
+				{ "op": "delete", "text": "First" },
+				{ "op": "insert", "text": "Final" },
+				{ "op": "equal", "text": " draft" },
+			])
+		);
+		assert_eq!(
+			diff["content"][1],
+			json!({ "op": "delete", "text": "quick" })
+		);
+
+		let post = app
+			.post(&format!("{path}/revisions/1/restore"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["revision"], 4);
+		assert_eq!(post["title"], "First draft");
+		assert_eq!(post["content"], "the quick brown fox");
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "jane@smith.com",
+				"username": "jane",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let page = app
+			.get(&format!("{path}/revisions"))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+		assert_eq!(page["items"][0]["number"], 4);
+
+		let response = app.get(&format!("{path}/revisions/1")).await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"revision_not_found"
+		);
+
+		app.post(&format!("{path}/revisions/4/restore"))
+			.await
+			.assert_status_not_found();
+	}
+
+	#[sqlx::test]
+	async fn test_if_match(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "first", "content": "" }))
+			.await;
+		let created = response.header("etag");
+		let path = format!(
+			"/posts/{}",
+			response.json::<serde_json::Value>()["id"].as_str().unwrap()
+		);
+
+		// Tags from `GET` responses can be used in `If-Match` as well
+		let fetched = app.get(&path).await.header("etag");
+		let response = app
+			.put(&path)
+			.add_header("if-match".parse().unwrap(), fetched)
+			.json(&json!({ "content": "edited" }))
+			.await;
+
+		response.assert_status_ok();
+
+		let updated = response.header("etag");
+
+		assert_ne!(updated, created);
+
+		// Comments and reactions by other users, bookmarks and rendering change the tag
+		// of the representation, but not the version of the post that `If-Match` compares
+		let fetched = app.get(&path).await.header("etag");
+
+		other
+			.post(&format!("{path}/comments"))
+			.json(&json!({ "content": "Nice post!" }))
+			.await
+			.assert_status_ok();
+		app.get(&path)
+			.add_header("if-none-match".parse().unwrap(), fetched.clone())
+			.await
+			.assert_status_ok();
+
+		let fetched = app.get(&path).await.header("etag");
+
+		other
+			.put(&format!("{path}/reactions/like"))
+			.await
+			.assert_status_ok();
+		app.put(&format!("{path}/bookmark"))
+			.await
+			.assert_status_ok();
+		app.get(&path)
+			.add_header("if-none-match".parse().unwrap(), fetched.clone())
+			.await
+			.assert_status_ok();
+
+		let response = app.get(&path).await;
+
+		assert_ne!(
+			app.get(&path)
+				.add_query_param("render", "html")
+				.await
+				.header("etag"),
+			response.header("etag")
+		);
+		app.get(&path)
+			.add_header("if-none-match".parse().unwrap(), response.header("etag"))
+			.await
+			.assert_status(StatusCode::NOT_MODIFIED);
+
+		let response = app
+			.put(&path)
+			.add_header("if-match".parse().unwrap(), fetched)
+			.json(&json!({ "title": "First" }))
+			.await;
+
+		response.assert_status_ok();
+
+		let updated = response.header("etag");
+
+		let response = app
+			.put(&path)
+			.add_header("if-match".parse().unwrap(), created.clone())
+			.json(&json!({ "content": "overwritten" }))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::PRECONDITION_FAILED);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"precondition_failed"
+		);
+
+		app.delete(&path)
+			.add_header("if-match".parse().unwrap(), created)
+			.await
+			.assert_status(StatusCode::PRECONDITION_FAILED);
+
+		assert_eq!(
+			app.get(&path).await.json::<serde_json::Value>()["content"],
+			"edited"
+		);
+
+		app.delete(&path)
+			.add_header("if-match".parse().unwrap(), updated)
+			.await
+			.assert_status_ok();
+	}
+
+	#[sqlx::test]
+	async fn test_patch(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "first", "content": "" }))
+			.await
+			.json::<serde_json::Value>();
+		let path = format!("/posts/{}", post["id"].as_str().unwrap());
+
+		assert!(post["published_at"].is_string());
+
+		let merge = |body: serde_json::Value| {
+			app.patch(&path)
+				.bytes(body.to_string().into())
+				.content_type("application/merge-patch+json")
+		};
+
+		// Missing fields are unchanged, and null clears nullable ones
+		let post = merge(json!({ "status": "archived", "published_at": null }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["status"], "archived");
+		assert_eq!(post["title"], "first");
+		assert!(post["published_at"].is_null());
+
+		merge(json!({ "title": null }))
+			.await
+			.assert_status(StatusCode::BAD_REQUEST);
+
+		let response = merge(json!({ "status": "scheduled", "published_at": null })).await;
+
+		response.assert_status(StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"invalid_publish_time"
+		);
+
+		let json_patch = |body: serde_json::Value| {
+			app.patch(&path)
+				.bytes(body.to_string().into())
+				.content_type("application/json-patch+json")
+		};
+
+		let post = json_patch(json!([
+			{ "op": "test", "path": "/title", "value": "first" },
+			{ "op": "replace", "path": "/title", "value": "second" },
+			{ "op": "add", "path": "/tags/-", "value": "rust" },
+		]))
+		.await
+		.json::<serde_json::Value>();
+
+		assert_eq!(post["title"], "second");
+		assert_eq!(post["slug"], "second");
+		assert_eq!(post["tags"], json!(["rust"]));
+		assert_eq!(post["status"], "archived");
+
+		let response =
+			json_patch(json!([{ "op": "test", "path": "/title", "value": "first" }])).await;
+
+		response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"invalid_json_patch"
+		);
+
+		json_patch(json!([{ "op": "remove", "path": "/title" }]))
+			.await
+			.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+		app.patch(&path)
+			.text("title=third")
+			.await
+			.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+	}
+
+	#[sqlx::test]
+	async fn test_not_modified(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "first", "content": "" }))
+			.await;
+		let path = format!(
+			"/posts/{}",
+			post.json::<serde_json::Value>()["id"].as_str().unwrap()
+		);
+
+		let response = app.get(&path).await;
+		let etag = response.header("etag");
+
+		// the version of the post from the write, followed by a hash of the body
+		let version = post.header("etag");
+
+		assert!(etag.to_str().unwrap().starts_with(&format!(
+			"{}.",
+			version.to_str().unwrap().trim_end_matches('"')
+		)));
+
+		let response = app
+			.get(&path)
+			.add_header("if-none-match".parse().unwrap(), etag.clone())
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+		assert_eq!(response.header("etag"), etag);
+		assert!(response.as_bytes().is_empty());
+
+		let response = app.get("/posts").await;
+		let etag = response.header("etag");
+
+		// Compressed responses share the tag of the uncompressed body
+		let response = app
+			.get("/posts")
+			.add_header("accept-encoding".parse().unwrap(), "gzip".parse().unwrap())
+			.add_header("if-none-match".parse().unwrap(), etag.clone())
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+		assert!(response.as_bytes().is_empty());
+
+		app.post("/posts")
+			.json(&json!({ "title": "second", "content": "" }))
+			.await;
+
+		app.get("/posts")
+			.add_header("if-none-match".parse().unwrap(), etag)
+			.await
+			.assert_status_ok();
+
+		let etag = app.get("/auth/me").await.header("etag");
+
+		app.get("/auth/me")
+			.add_header("if-none-match".parse().unwrap(), etag)
+			.await
+			.assert_status(StatusCode::NOT_MODIFIED);
+	}
+
+	#[sqlx::test]
+	async fn test_updated_since(pool: Database) {
+		let anonymous = app(pool.clone());
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let mut posts = Vec::new();
+
+		for title in ["first", "second"] {
+			let post = app
+				.post("/posts")
+				.json(&json!({ "title": title, "content": "" }))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(post["updated_at"], post["created_at"]);
+			posts.push(post);
+		}
+
+		let since = posts[1]["updated_at"].as_str().unwrap().to_string();
+		let path = format!("/posts/{}", posts[0]["id"].as_str().unwrap());
+
+		let post = app
+			.put(&path)
+			.json(&json!({ "content": "edited" }))
+			.await
+			.json::<serde_json::Value>();
+
+		let time = |value: &serde_json::Value| {
+			value
+				.as_str()
+				.unwrap()
+				.parse::<chrono::DateTime<chrono::Utc>>()
+				.unwrap()
+		};
+
+		assert!(time(&post["updated_at"]) > time(&posts[1]["updated_at"]));
+
+		for path in ["/posts", "/posts/me"] {
+			let page = app
+				.get(path)
+				.add_query_param("updated_since", &since)
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(page["total"], 1);
+			assert_eq!(page["items"][0]["title"], "first");
+		}
+
+		let page = app
+			.get("/posts")
+			.add_query_param("sort", "-updated_at")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "first");
+
+		// comments don't change `updated_at`, so posts are only revalidated by their body
+		let response = anonymous.get(&path).await;
+
+		assert!(response.maybe_header("last-modified").is_none());
+		assert_eq!(response.header("vary"), "Cookie, Authorization");
+
+		app.post(&format!("{path}/comments"))
+			.json(&json!({ "content": "First!" }))
+			.await
+			.assert_status_ok();
+
+		let response = anonymous
+			.get(&path)
+			.add_header("if-none-match".parse().unwrap(), response.header("etag"))
+			.await;
+
+		response.assert_status_ok();
+		assert_eq!(response.json::<serde_json::Value>()["comment_count"], 1);
+	}
+
+	#[sqlx::test]
+	async fn test_reactions(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let mut ids = Vec::new();
+
+		for title in ["first", "second"] {
+			let post = app
+				.post("/posts")
+				.json(&json!({ "title": title, "content": "" }))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(post["reactions"], json!({}));
+			ids.push(post["id"].as_str().unwrap().to_string());
+		}
+
+		let post = app
+			.put(&format!("/posts/{}/reactions/like", ids[0]))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["reactions"], json!({ "like": 1 }));
+		assert_eq!(post["reacted"], json!(["like"]));
+
+		// reacting twice with the same kind has no effect
+		for kind in ["like", "love", "like"] {
+			other
+				.put(&format!("/posts/{}/reactions/{kind}", ids[0]))
+				.await
+				.assert_status_ok();
+		}
+
+		let post = other
+			.get(&format!("/posts/{}", ids[0]))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["reactions"], json!({ "like": 2, "love": 1 }));
+		assert_eq!(post["reacted"], json!(["like", "love"]));
+		// reactions are counted without counting as edits to the post
+		assert_eq!(post["updated_at"], post["created_at"]);
+
+		let page = app
+			.get("/posts")
+			.add_query_param("sort", "-likes")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["title"], "first");
+		assert_eq!(page["items"][0]["reacted"], json!(["like"]));
+		assert_eq!(page["items"][1]["reacted"], json!([]));
+
+		let post = other
+			.delete(&format!("/posts/{}/reactions/love", ids[0]))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["reactions"], json!({ "like": 2 }));
+		assert_eq!(post["reacted"], json!(["like"]));
+
+		app.put(&format!("/posts/{}/reactions/angry", ids[0]))
+			.await
+			.assert_status(StatusCode::BAD_REQUEST);
+	}
+
+	#[sqlx::test]
+	async fn test_render_html(pool: Database) {
+		let app = app(pool.clone());
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let post = app
+			.post("/posts")
+			.json(&json!({
+				"title": "markdown",
+				"content": "# Hello\n\n<script>alert(1)</script>\n\n**world**",
+			}))
+			.await
+			.json::<serde_json::Value>();
+
+		assert!(post.get("content_html").is_none());
+
+		let path = format!("/posts/{}", post["id"].as_str().unwrap());
+		let post = app
+			.get(&path)
+			.add_query_param("render", "html")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(
+			post["content_html"],
+			"<h1>Hello</h1>\n\n<p><strong>world</strong></p>\n"
+		);
+
+		let cached = sqlx::query_scalar!("SELECT content_html FROM post_revision")
+			.fetch_one(&pool)
+			.await
+			.unwrap();
+
+		assert_eq!(cached.as_deref(), post["content_html"].as_str());
+
+		let page = app
+			.get("/posts")
+			.add_query_param("render", "html")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["content_html"], post["content_html"]);
+
+		let response = app
+			.put(&path)
+			.json(&json!({ "content": "> ".repeat(64) }))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"markdown_too_deep"
+		);
+	}
+
+	#[sqlx::test]
+	async fn test_visibility(pool: Database) {
+		let anonymous = app(pool.clone());
+		let other = app(pool.clone());
+		let app = app(pool.clone());
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		let mut ids = Vec::new();
+
+		for visibility in ["public", "unlisted", "followers", "private"] {
+			let post = app
+				.post("/posts")
+				.json(&json!({
+					"title": format!("{visibility} post"),
+					"content": "visibility",
+					"visibility": visibility,
+				}))
+				.await
+				.json::<serde_json::Value>();
+
+			assert_eq!(post["visibility"], visibility);
+			ids.push(post["id"].as_str().unwrap().to_string());
+		}
+
+		let titles = |page: serde_json::Value| {
+			page["items"]
+				.as_array()
+				.unwrap()
+				.iter()
+				.map(|post| post["title"].as_str().unwrap().to_string())
+				.collect::<Vec<_>>()
+		};
+
+		// authors see every one of their posts
+		let page = app.get("/posts").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 4);
+
+		let page = other.get("/posts").await.json::<serde_json::Value>();
+
+		assert_eq!(titles(page), ["public post"]);
+
+		for (id, found) in ids.iter().zip([true, true, false, false]) {
+			let response = other.get(&format!("/posts/{id}")).await;
+
+			if found {
+				response.assert_status_ok();
+			} else {
+				response.assert_status_not_found();
+				assert_eq!(
+					response.json::<serde_json::Value>()[0]["code"],
+					"post_not_found"
+				);
+			}
+		}
+
+		sqlx::query(
+			r#"
+				INSERT INTO follow (follower_id, followee_id)
+				SELECT follower.id, followee.id FROM "user" follower, "user" followee
+				WHERE follower.username = 'jane' AND followee.username = 'john'
+			"#,
+		)
+		.execute(&pool)
+		.await
+		.unwrap();
+
+		let page = other.get("/posts").await.json::<serde_json::Value>();
+
+		assert_eq!(titles(page), ["followers post", "public post"]);
+
+		let page = other
+			.get("/posts/search")
+			.add_query_param("q", "visibility")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 2);
+
+		other
+			.get(&format!("/posts/{}", ids[2]))
+			.await
+			.assert_status_ok();
+		other
+			.get(&format!("/users/john/posts/{}", "followers-post"))
+			.await
+			.assert_status_ok();
+		anonymous
+			.get(&format!("/posts/{}", ids[2]))
+			.await
+			.assert_status_not_found();
+		other
+			.get(&format!("/posts/{}", ids[3]))
+			.await
+			.assert_status_not_found();
+		other
+			.get(&format!("/posts/{}/comments", ids[3]))
+			.await
+			.assert_status_not_found();
+		other
+			.put(&format!("/posts/{}/reactions/like", ids[3]))
+			.await
+			.assert_status_not_found();
+
+		app.put(&format!("/posts/{}", ids[3]))
+			.json(&json!({ "visibility": "public" }))
+			.await
+			.assert_status_ok();
+
+		anonymous
+			.get(&format!("/posts/{}", ids[3]))
+			.await
+			.assert_status_ok();
+	}
+
+	#[sqlx::test]
+	async fn test_syndication(pool: Database) {
+		let anonymous = app(pool.clone());
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		for (title, visibility, status) in [
+			("Fish & Chips", "public", "published"),
+			("Unlisted", "unlisted", "published"),
+			("Private", "private", "published"),
+			("Draft", "public", "draft"),
+		] {
+			app.post("/posts")
+				.json(&json!({
+					"title": title,
+					"content": "Hello **world**",
+					"tags": ["food"],
+					"visibility": visibility,
+					"status": status,
+				}))
+				.await
+				.assert_status_ok();
+		}
+
+		let response = anonymous.get("/posts/feed.atom").await;
+		let atom = response.text();
+
+		response.assert_status_ok();
+		assert_eq!(
+			response.header("content-type"),
+			"application/atom+xml; charset=utf-8"
+		);
+		assert!(atom.starts_with(
+			r#"<?xml version="1.0" encoding="utf-8"?><feed xmlns="http://www.w3.org/2005/Atom">"#
+		));
+		assert!(atom.contains("<title type=\"text\">Fish &amp; Chips</title>"));
+		assert!(atom.contains("/users/john/posts/fish-chips"));
+		assert!(atom.contains("&lt;strong&gt;world&lt;/strong&gt;"));
+		assert!(atom.contains(r#"<category term="food"/>"#));
+		assert_eq!(atom.matches("<entry>").count(), 1);
+
+		let response = anonymous.get("/users/john/feed.rss").await;
+		let rss = response.text();
+
+		assert_eq!(
+			response.header("content-type"),
+			"application/rss+xml; charset=utf-8"
+		);
+		assert!(rss.contains("<dc:creator>john</dc:creator>"));
+		assert!(rss.contains("<title>Posts by john</title>"));
+		assert_eq!(rss.matches("<item>").count(), 1);
+
+		// feeds are cached like JSON responses
+		let etag = response.header("etag");
+
+		anonymous
+			.get("/users/john/feed.rss")
+			.add_header("if-none-match".parse().unwrap(), etag)
+			.await
+			.assert_status(StatusCode::NOT_MODIFIED);
+		anonymous
+			.get("/users/john/feed.atom")
+			.add_header(
+				"if-modified-since".parse().unwrap(),
+				response.header("last-modified"),
+			)
+			.await
+			.assert_status(StatusCode::NOT_MODIFIED);
+
+		// deleting the only listed post moves the date of the feed forward, not back
+		let updated = |atom: &str| {
+			let start = atom.find("<updated>").unwrap() + "<updated>".len();
+			let end = atom.find("</updated>").unwrap();
+
+			atom[start..end]
+				.parse::<chrono::DateTime<chrono::Utc>>()
+				.unwrap()
+		};
+		let before = updated(&atom);
+		let post = app
+			.get("/users/john/posts/fish-chips")
+			.await
+			.json::<serde_json::Value>();
+
+		app.delete(&format!("/posts/{}", post["id"].as_str().unwrap()))
+			.await
+			.assert_status_ok();
+
+		let atom = anonymous.get("/posts/feed.atom").await.text();
+
+		assert_eq!(atom.matches("<entry>").count(), 0);
+		assert!(updated(&atom) >= before);
+
+		let response = anonymous.get("/users/jane/feed.atom").await;
+
+		response.assert_status_not_found();
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"user_not_found"
+		);
+	}
+
+	#[sqlx::test]
+	async fn test_filters(pool: Database) {
+		let config = toml::from_str(
+			r#"
+				[banned_words]
+				words = ["Casino", "free  money"]
+
+				[link_limit]
+				max = 1
+
+				[duplicate_content]
+				hours = 24
+
+				[rate_limit]
+				posts_per_hour = 3
+			"#,
+		)
+		.unwrap();
+		let app = app_with_filters(pool, super::filter::Chain::from_config(config));
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await
+			.assert_status_ok();
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "Win big", "content": "Visit my CASINO today" }))
+			.await;
+
+		response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+		let error = &response.json::<serde_json::Value>()[0];
+
+		assert_eq!(error["code"], "banned_word");
+		assert_eq!(error["details"]["rule"], "banned_words");
+		assert_eq!(error["details"]["word"], "casino");
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "Offer", "content": "Get FREE\nmoney, today only" }))
+			.await;
+
+		response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["details"]["word"],
+			"free money"
+		);
+
+		let response = app
+			.post("/posts")
+			.json(&json!({
+				"title": "Links",
+				"content": "See https://a.com and HTTP://b.com",
+			}))
+			.await;
+
+		response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+		let error = &response.json::<serde_json::Value>()[0];
+
+		assert_eq!(error["code"], "too_many_links");
+		assert_eq!(error["details"]["rule"], "link_limit");
+		assert_eq!(error["details"]["count"], 2);
+
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "Hello", "content": "Hello  world" }))
+			.await
+			.json::<serde_json::Value>();
+		let id = post["id"].as_str().unwrap();
+
+		// whitespace and case are ignored when looking for duplicates
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "Again", "content": " hello world\n" }))
+			.await;
+
+		response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+		let error = &response.json::<serde_json::Value>()[0];
+
+		assert_eq!(error["code"], "duplicate_content");
+		assert_eq!(error["details"]["key"], id);
+
+		// a post is not a duplicate of itself, but updates are still checked
+		app.put(&format!("/posts/{id}"))
+			.json(&json!({ "title": "Hello again" }))
+			.await
+			.assert_status_ok();
+		app.patch(&format!("/posts/{id}"))
+			.bytes(json!({ "content": "casino" }).to_string().into())
+			.content_type("application/merge-patch+json")
+			.await
+			.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+		for content in ["Second", "Third"] {
+			app.post("/posts")
+				.json(&json!({ "title": content, "content": content }))
+				.await
+				.assert_status_ok();
+		}
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "Fourth", "content": "Fourth" }))
+			.await;
+
+		response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+		assert!(
+			response
+				.header("retry-after")
+				.to_str()
+				.unwrap()
+				.parse::<u64>()
+				.unwrap() > 0
+		);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["details"]["rule"],
+			"rate_limit"
+		);
+
+		// deleted posts still count towards the limit
+		app.delete(&format!("/posts/{id}")).await.assert_status_ok();
+		app.post("/posts")
+			.json(&json!({ "title": "Fifth", "content": "Fifth" }))
+			.await
+			.assert_status(StatusCode::TOO_MANY_REQUESTS);
+	}
+
+	#[sqlx::test]
+	async fn test_rate_limit_concurrent(pool: Database) {
+		// a server only sends one request at a time, so each request gets its own
+		let servers = (0..4)
+			.map(|_| {
+				let config = toml::from_str("[rate_limit]\nposts_per_hour = 3").unwrap();
+
+				app_with_filters(pool.clone(), super::filter::Chain::from_config(config))
+			})
+			.collect::<Vec<_>>();
+
+		servers[0]
+			.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await
+			.assert_status_ok();
+
+		for server in &servers[1..] {
+			server
+				.post("/auth/login")
+				.json(&json!({ "email": "john@smith.com", "password": "hunter2hunter" }))
+				.await
+				.assert_status_ok();
+		}
+
+		let create = |server: &TestServer| {
+			server
+				.post("/posts")
+				.json(&json!({ "title": "Post", "content": "" }))
+				.into_future()
+		};
+		let responses = tokio::join!(
+			create(&servers[0]),
+			create(&servers[1]),
+			create(&servers[2]),
+			create(&servers[3]),
+		);
+		let created = [responses.0, responses.1, responses.2, responses.3]
+			.iter()
+			.filter(|response| response.status_code() == StatusCode::OK)
+			.count();
+
+		assert_eq!(created, 3);
+	}
+
+	#[sqlx::test]
+	async fn test_bulk(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await
+				.assert_status_ok();
+		}
+
+		let mut ids = Vec::new();
+
+		for (title, status) in [("Draft", "draft"), ("Published", "published")] {
+			let post = app
+				.post("/posts")
+				.json(&json!({ "title": title, "content": "", "status": status }))
+				.await
+				.json::<serde_json::Value>();
+
+			ids.push(post["id"].as_str().unwrap().to_owned());
+		}
+
+		let theirs = other
+			.post("/posts")
+			.json(&json!({ "title": "Jane's", "content": "" }))
+			.await
+			.json::<serde_json::Value>();
+		let theirs = theirs["id"].as_str().unwrap();
+
+		let results = app
+			.post("/posts/bulk")
+			.json(&json!({
+				"operations": [
+					{ "op": "publish", "id": ids[0] },
+					{ "op": "retag", "id": ids[1], "tags": ["Rust Lang"] },
+					{ "op": "delete", "id": theirs },
+					{ "op": "delete", "id": ids[1] },
+					{ "op": "retag", "id": ids[1], "tags": [] },
+				],
+			}))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(results[0]["ok"], true);
+		assert_eq!(results[0]["post"]["status"], "published");
+		assert_eq!(results[1]["post"]["tags"], json!(["rust-lang"]));
+
+		// neither operation changes the title or content, so no revisions are created
+		assert_eq!(results[0]["post"]["revision"], 1);
+		assert_eq!(results[1]["post"]["revision"], 1);
+		assert_eq!(
+			app.get(&format!("/posts/{}/revisions", ids[0]))
+				.await
+				.json::<serde_json::Value>()["total"],
+			1
+		);
+
+		// failures are reported for each operation, without undoing the others
+		assert_eq!(results[2]["ok"], false);
+		assert_eq!(results[2]["id"], theirs);
+		assert_eq!(results[2]["error"]["code"], "post_not_found");
+		assert_eq!(results[3]["ok"], true);
+		assert!(results[3].get("post").is_none());
+		assert_eq!(results[4]["error"]["code"], "post_not_found");
+
+		other
+			.get(&format!("/posts/{theirs}"))
+			.await
+			.assert_status_ok();
+		app.get(&format!("/posts/{}", ids[1]))
+			.await
+			.assert_status_not_found();
+		assert_eq!(
+			app.get(&format!("/posts/{}", ids[0]))
+				.await
+				.json::<serde_json::Value>()["status"],
+			"published"
+		);
+
+		let operations = |count: usize| {
+			let operations = (0..count)
+				.map(|_| json!({ "op": "publish", "id": ids[0] }))
+				.collect::<Vec<_>>();
+
+			json!({ "operations": operations })
+		};
+
+		for body in [
+			operations(0),
+			operations(101),
+			json!({ "operations": [{ "op": "retag", "id": ids[0], "tags": vec!["tag"; 11] }] }),
+			json!({ "operations": [{ "op": "rename", "id": ids[0] }] }),
+		] {
+			app.post("/posts/bulk")
+				.json(&body)
+				.await
+				.assert_status(StatusCode::BAD_REQUEST);
+		}
+
+		let response = app
+			.post("/posts/bulk")
+			.json(&json!({
+				"operations": [{ "op": "retag", "id": ids[0], "tags": vec!["tag"; 11] }],
+			}))
+			.await;
+
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"too_many_tags"
+		);
+
+		let response = app
+			.post("/posts/bulk")
+			.json(&json!({
+				"operations": [{ "op": "retag", "id": ids[0], "tags": ["a".repeat(300 * 1024)] }],
+			}))
+			.await;
+
+		response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"payload_too_large"
+		);
 	}
 }
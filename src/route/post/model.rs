@@ -1,14 +1,30 @@
-pub use crate::route::model::{IdInput, Paginate};
+pub use crate::route::model::{IdInput, Keyset, Sort, SortKey, UpdatedSinceInput};
 
+use crate::{
+	error,
+	extract::{ETag, Json, Tagged},
+	markdown::validate_markdown,
+	route::tag::model::{normalize, validate_name, validate_tags},
+};
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use macros::model;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sqlx::{
+	error::BoxDynError,
+	postgres::{PgHasArrayType, PgTypeInfo, PgValueRef},
+	types::Json as JsonValue,
+	Decode, Postgres, QueryBuilder,
+};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 /// A single post, created by a user.
 #[model]
-#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, sqlx::FromRow)]
 pub struct Post {
 	/// The unique identifier of the post.
 	#[serde(skip_deserializing)]
@@ -19,9 +35,817 @@ pub struct Post {
 	/// The title of the post.
 	#[validate(length(min = 3, max = 128))]
 	pub title: String,
+	/// A URL-safe identifier generated from the title, unique among the posts of the author.
+	/// Previous slugs redirect to the current one when the title changes.
+	#[serde(skip_deserializing)]
+	pub slug: String,
 	/// The content of the post in Markdown format.
+	#[validate(length(max = 100_000), custom(function = "validate_markdown"))]
 	pub content: String,
+	/// The content rendered as sanitised HTML, only included with `render=html`.
+	#[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+	#[sqlx(default)]
+	pub content_html: Option<String>,
+	/// The names of the tags on the post. Tags are normalised to lowercase
+	/// with hyphens between words, and created when first used.
+	#[serde(default)]
+	#[validate(length(max = 10), custom(function = "validate_tags"))]
+	pub tags: Vec<String>,
+	/// The publication status of the post. Only published posts are visible
+	/// to users other than the author, depending on the `visibility`.
+	#[serde(default)]
+	pub status: PostStatus,
+	/// Who can see the post once it is published.
+	#[serde(default)]
+	pub visibility: Visibility,
+	/// Whether the post was hidden by a moderator, or after being reported by several users.
+	/// Hidden posts are only visible to their author and admins.
+	#[serde(skip_deserializing)]
+	pub hidden: bool,
+	/// When the post was published, or will be published if it is scheduled.
+	/// Defaults to the time the post is published.
+	pub published_at: Option<DateTime<Utc>>,
+	/// The number of the current revision of the post, starting at 1.
+	#[serde(skip_deserializing)]
+	pub revision: i32,
+	/// The number of comments on the post, including replies.
+	#[serde(skip_deserializing)]
+	pub comment_count: i32,
+	/// The number of reactions of each kind on the post.
+	#[serde(skip_deserializing)]
+	pub reactions: Reactions,
+	/// The kinds of reactions that you left on the post, which is always empty
+	/// for anonymous viewers.
+	#[serde(skip_deserializing)]
+	#[sqlx(default)]
+	pub reacted: Vec<ReactionKind>,
+	/// Whether you bookmarked the post, which is always `false` for anonymous viewers.
+	#[serde(skip_deserializing)]
+	#[sqlx(default)]
+	pub bookmarked: bool,
 	/// The creation time of the post.
 	#[serde(skip_deserializing)]
 	pub created_at: chrono::DateTime<chrono::Utc>,
+	/// The last time the post was changed.
+	#[serde(skip_deserializing)]
+	pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Post {
+	/// The columns selected for a [`Post`] in dynamic queries.
+	pub const SELECT: &'static str =
+		"SELECT id, user_id, title, slug, content, status, visibility, hidden, published_at, revision, comment_count, reactions, \
+		created_at, updated_at, post_tags(id) AS tags";
+
+	/// Returns the version tag of the post, which only covers the fields that the author can
+	/// change. Counters and fields that depend on the viewer, such as `reactions` and
+	/// `content_html`, are left out so that they do not fail the author's `If-Match`.
+	/// `GET` responses combine it with a hash of the whole body for `If-None-Match`.
+	pub fn etag(&self) -> ETag {
+		ETag::of(&(
+			self.id,
+			self.revision,
+			self.status,
+			self.visibility,
+			self.published_at,
+			&self.tags,
+		))
+	}
+
+	/// Responds with the post as JSON, along with its `ETag` header. The `reacted` and
+	/// `bookmarked` fields depend on the viewer, so the response varies by their credentials.
+	/// There is no `Last-Modified` header, since `updated_at` doesn't change with the counters.
+	pub fn tagged(self) -> Tagged<Json<Self>> {
+		Tagged::new(self.etag(), Json(self)).vary("Cookie, Authorization")
+	}
+}
+
+/// The maximum length of a slug, excluding any collision suffix.
+const SLUG_LENGTH: usize = 56;
+
+/// Turns a title into a URL-safe slug of lowercase ASCII letters and digits
+/// separated by hyphens, such as `hello-world` for "Hello, World!".
+pub fn slugify(title: &str) -> String {
+	let slug = title
+		.split(|c: char| !c.is_ascii_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(str::to_ascii_lowercase)
+		.collect::<Vec<_>>()
+		.join("-");
+
+	let slug = slug[..slug.len().min(SLUG_LENGTH)].trim_end_matches('-');
+
+	if slug.is_empty() {
+		"post".into()
+	} else {
+		slug.into()
+	}
+}
+
+/// Returns `slug` if it is not taken, or else the first of `slug-2`, `slug-3`, ... that is not.
+pub fn next_slug(slug: &str, taken: &[String]) -> String {
+	if !taken.iter().any(|taken| taken == slug) {
+		return slug.into();
+	}
+
+	// At most `taken.len()` of the candidates can be taken, so one of them is always free
+	(2..=taken.len() + 2)
+		.map(|n| format!("{slug}-{n}"))
+		.find(|candidate| !taken.contains(candidate))
+		.unwrap_or_default()
+}
+
+/// A kind of reaction to a post.
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Deserialize,
+	Serialize,
+	JsonSchema,
+	sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "reaction_kind", rename_all = "snake_case")]
+pub enum ReactionKind {
+	Like,
+	Love,
+	Laugh,
+	Wow,
+	Sad,
+}
+
+impl PgHasArrayType for ReactionKind {
+	fn array_type_info() -> PgTypeInfo {
+		PgTypeInfo::with_name("_reaction_kind")
+	}
+}
+
+/// The number of reactions of each kind on a post. Kinds without any reactions are omitted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Reactions(BTreeMap<ReactionKind, i32>);
+
+impl Reactions {
+	pub fn count(&self, kind: ReactionKind) -> i32 {
+		self.0.get(&kind).copied().unwrap_or_default()
+	}
+}
+
+// Stored as a JSONB object in `post.reactions`, which is kept up to date by a trigger
+impl sqlx::Type<Postgres> for Reactions {
+	fn type_info() -> PgTypeInfo {
+		JsonValue::<BTreeMap<ReactionKind, i32>>::type_info()
+	}
+
+	fn compatible(ty: &PgTypeInfo) -> bool {
+		JsonValue::<BTreeMap<ReactionKind, i32>>::compatible(ty)
+	}
+}
+
+impl<'r> Decode<'r, Postgres> for Reactions {
+	fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+		JsonValue::<BTreeMap<ReactionKind, i32>>::decode(value).map(|json| Self(json.0))
+	}
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct ReactionInput {
+	/// The unique identifier of the post.
+	pub id: Uuid,
+	/// The kind of reaction.
+	pub kind: ReactionKind,
+}
+
+/// The publication status of a post.
+#[derive(
+	Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "post_status", rename_all = "snake_case")]
+pub enum PostStatus {
+	/// Only visible to the author.
+	Draft,
+	/// Visible to everyone.
+	#[default]
+	Published,
+	/// Only visible to the author until `published_at`, when it is published automatically.
+	Scheduled,
+	/// Only visible to the author, after having been published.
+	Archived,
+}
+
+/// Who can see a post once it is published. Authors can always see their own posts,
+/// and posts that are hidden from a viewer behave as if they do not exist.
+#[derive(
+	Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "post_visibility", rename_all = "snake_case")]
+pub enum Visibility {
+	/// Visible to everyone, and included in lists and searches.
+	#[default]
+	Public,
+	/// Visible to everyone with a link, but left out of lists and searches.
+	Unlisted,
+	/// Only visible to the followers of the author.
+	Followers,
+	/// Only visible to the author.
+	Private,
+}
+
+/// Pushes a condition that only matches posts listed for `viewer`: published posts
+/// they can see other than unlisted ones, and every post of the viewer themselves.
+pub fn push_listed(query: &mut QueryBuilder<'_, Postgres>, viewer: Option<Uuid>) {
+	query
+		.push("post_listed(user_id, status, visibility, hidden, ")
+		.push_bind(viewer)
+		.push(")");
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct KeyInput {
+	/// The unique id or slug of the post.
+	pub id: String,
+}
+
+/// A version of the title and content of a post. A revision is created
+/// for every update that changes either of them, and is never modified afterwards.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Revision {
+	#[serde(skip)]
+	pub id: Uuid,
+	/// The number of the revision, starting at 1 when the post is created.
+	pub number: i32,
+	/// The title of the post at this revision.
+	pub title: String,
+	/// The content of the post at this revision.
+	pub content: String,
+	/// The creation time of the revision.
+	pub created_at: DateTime<Utc>,
+}
+
+impl Revision {
+	/// The columns selected for a [`Revision`] in dynamic queries.
+	pub const SELECT: &'static str = "SELECT id, number, title, content, created_at";
+}
+
+impl Keyset for Revision {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Number(f64::from(self.number))
+	}
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct RevisionInput {
+	/// The unique id of the post.
+	pub id: Uuid,
+	/// The number of the revision.
+	pub rev: i32,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct DiffInput {
+	/// The number of the older revision.
+	pub from: i32,
+	/// The number of the newer revision.
+	pub to: i32,
+}
+
+/// The word-level differences between two revisions of a post.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Diff {
+	/// The number of the older revision.
+	pub from: i32,
+	/// The number of the newer revision.
+	pub to: i32,
+	/// The changes to the title.
+	pub title: Vec<Change>,
+	/// The changes to the content.
+	pub content: Vec<Change>,
+}
+
+impl Diff {
+	pub fn new(from: &Revision, to: &Revision) -> Self {
+		Self {
+			from: from.number,
+			to: to.number,
+			title: diff_words(&from.title, &to.title),
+			content: diff_words(&from.content, &to.content),
+		}
+	}
+}
+
+/// A run of text that is the same in both revisions, or only in one of them.
+/// Concatenating the `equal` and `delete` changes gives the older text, and
+/// the `equal` and `insert` changes give the newer text.
+#[derive(Debug, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Change {
+	pub op: ChangeOp,
+	pub text: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+	Equal,
+	Insert,
+	Delete,
+}
+
+/// Diffs two texts word by word, merging consecutive words with the same change.
+pub fn diff_words(old: &str, new: &str) -> Vec<Change> {
+	let diff = similar::TextDiff::from_words(old, new);
+	let mut changes = Vec::<Change>::new();
+
+	for change in diff.iter_all_changes() {
+		let op = match change.tag() {
+			similar::ChangeTag::Equal => ChangeOp::Equal,
+			similar::ChangeTag::Insert => ChangeOp::Insert,
+			similar::ChangeTag::Delete => ChangeOp::Delete,
+		};
+
+		match changes.last_mut() {
+			Some(last) if last.op == op => last.text.push_str(change.value()),
+			_ => changes.push(Change {
+				op,
+				text: change.value().into(),
+			}),
+		}
+	}
+
+	changes
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct StatusInput {
+	/// Only return posts with this status.
+	pub status: Option<PostStatus>,
+}
+
+/// A format that the content of posts can be rendered to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderFormat {
+	/// `CommonMark` with tables and strikethrough, sanitised against an allowlist of tags.
+	Html,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct RenderInput {
+	/// Also return the content rendered in this format, such as `content_html`.
+	pub render: Option<RenderFormat>,
+}
+
+impl RenderInput {
+	pub fn html(&self) -> bool {
+		self.render == Some(RenderFormat::Html)
+	}
+}
+
+impl Keyset for Post {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, column: &str) -> SortKey {
+		match column {
+			"title" => SortKey::Text(self.title.clone()),
+			"updated_at" => SortKey::Timestamp(self.updated_at),
+			"published_at" => SortKey::Timestamp(self.published_at.unwrap_or(self.created_at)),
+			"like_count" => SortKey::Number(self.reactions.count(ReactionKind::Like).into()),
+			_ => SortKey::Timestamp(self.created_at),
+		}
+	}
+}
+
+/// The order to list posts in.
+#[derive(Clone, Copy, Default, Deserialize, JsonSchema)]
+pub enum PostSort {
+	/// Oldest first.
+	#[serde(rename = "created_at")]
+	CreatedAt,
+	/// Newest first.
+	#[default]
+	#[serde(rename = "-created_at")]
+	CreatedAtDesc,
+	/// Alphabetically by title.
+	#[serde(rename = "title")]
+	Title,
+	/// Least recently changed first, for syncing changes in order.
+	#[serde(rename = "updated_at")]
+	UpdatedAt,
+	/// Most recently changed first.
+	#[serde(rename = "-updated_at")]
+	UpdatedAtDesc,
+	/// Most liked first.
+	#[serde(rename = "-likes")]
+	LikesDesc,
+}
+
+impl From<PostSort> for Sort {
+	fn from(sort: PostSort) -> Self {
+		match sort {
+			PostSort::CreatedAt => Sort::ascending("created_at"),
+			PostSort::CreatedAtDesc => Sort::NEWEST,
+			PostSort::Title => Sort::ascending("title"),
+			PostSort::UpdatedAt => Sort::ascending("updated_at"),
+			PostSort::UpdatedAtDesc => Sort::descending("updated_at"),
+			PostSort::LikesDesc => Sort::descending("like_count"),
+		}
+	}
+}
+
+fn validate_created_range(filter: &FilterInput) -> Result<(), ValidationError> {
+	if let (Some(after), Some(before)) = (filter.created_after, filter.created_before) {
+		if after >= before {
+			return Err(ValidationError::new(
+				"created_after must be before created_before",
+			));
+		}
+	}
+
+	Ok(())
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+#[validate(schema(function = "validate_created_range"))]
+pub struct FilterInput {
+	/// The order to return posts in, newest first by default.
+	#[serde(default)]
+	pub sort: PostSort,
+	/// Only return posts written by the user with this username.
+	#[validate(length(min = 3, max = 16))]
+	pub author: Option<String>,
+	/// Only return posts created strictly after this time.
+	pub created_after: Option<DateTime<Utc>>,
+	/// Only return posts created strictly before this time.
+	pub created_before: Option<DateTime<Utc>>,
+	/// Only return posts with this tag.
+	#[validate(custom(function = "validate_name"))]
+	pub tag: Option<String>,
+}
+
+impl FilterInput {
+	/// Pushes a condition for every filter onto a query that already
+	/// ends in a `WHERE` clause. Every value is bound as a parameter.
+	pub fn push_filters(&self, query: &mut QueryBuilder<'_, Postgres>) {
+		if let Some(ref author) = self.author {
+			query
+				.push(r#" AND user_id = (SELECT id FROM "user" WHERE username = "#)
+				.push_bind(author.clone())
+				.push(")");
+		}
+
+		if let Some(after) = self.created_after {
+			query.push(" AND created_at > ").push_bind(after);
+		}
+
+		if let Some(before) = self.created_before {
+			query.push(" AND created_at < ").push_bind(before);
+		}
+
+		if let Some(ref tag) = self.tag {
+			query
+				.push(" AND id IN (SELECT post_id FROM post_tag WHERE tag_id = ")
+				.push(" (SELECT id FROM tag WHERE name = ")
+				.push_bind(normalize(tag))
+				.push("))");
+		}
+	}
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct SearchInput {
+	/// The search query. Words are matched by prefix, and
+	/// phrases in double quotes are matched exactly.
+	#[validate(length(min = 1, max = 256))]
+	pub q: String,
+}
+
+impl SearchInput {
+	/// Converts the query into the `tsquery` syntax, where every word
+	/// is matched by prefix and every quoted phrase is matched in order.
+	///
+	/// Anything other than letters and digits is treated as a separator,
+	/// so the result is always a valid `tsquery`.
+	pub fn to_tsquery(&self) -> String {
+		self.q
+			.split('"')
+			.enumerate()
+			.flat_map(|(i, part)| {
+				let words = part
+					.split(|c: char| !c.is_alphanumeric())
+					.filter(|word| !word.is_empty());
+
+				// Every odd part is inside of a pair of quotes
+				if i % 2 == 1 {
+					let phrase = words.collect::<Vec<_>>().join(" <-> ");
+
+					(!phrase.is_empty())
+						.then(|| format!("({phrase})"))
+						.into_iter()
+						.collect::<Vec<_>>()
+				} else {
+					words.map(|word| format!("{word}:*")).collect()
+				}
+			})
+			.collect::<Vec<_>>()
+			.join(" & ")
+	}
+}
+
+/// A post that matched a search query.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct SearchResult {
+	#[serde(flatten)]
+	#[sqlx(flatten)]
+	pub post: Post,
+	/// How closely the post matched the query, where higher is better.
+	pub rank: f32,
+	/// An excerpt of the content around the matches, as HTML with every match
+	/// wrapped in `<mark>` tags. The content itself is escaped, so it is safe to render.
+	pub snippet: String,
+}
+
+impl SearchResult {
+	/// The columns selected for a [`SearchResult`], from a subquery
+	/// that exposes the `query` and its `rank` next to each post.
+	pub const SELECT: &'static str = "SELECT id, user_id, title, slug, content, status, visibility, \
+		hidden, published_at, revision, comment_count, reactions, created_at, updated_at, post_tags(id) AS tags, rank, \
+		ts_headline('english', escape_html(content), query, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS snippet";
+}
+
+/// A post that you bookmarked.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Bookmark {
+	#[serde(flatten)]
+	#[sqlx(flatten)]
+	pub post: Post,
+	/// When you bookmarked the post.
+	pub bookmarked_at: DateTime<Utc>,
+}
+
+impl Bookmark {
+	/// The columns selected for a [`Bookmark`], from a subquery
+	/// that exposes the `bookmarked_at` time next to each post.
+	pub const SELECT: &'static str =
+		"SELECT id, user_id, title, slug, content, status, visibility, \
+		hidden, published_at, revision, comment_count, reactions, created_at, updated_at, post_tags(id) AS tags, \
+		bookmarked_at";
+}
+
+/// A post in an Atom or RSS feed, along with the username of its author.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SyndicatedPost {
+	#[sqlx(flatten)]
+	pub post: Post,
+	pub username: String,
+}
+
+impl SyndicatedPost {
+	/// The columns selected for a [`SyndicatedPost`] from the `post` table.
+	pub const SELECT: &'static str =
+		"SELECT id, user_id, title, slug, content, status, visibility, \
+		hidden, published_at, revision, comment_count, reactions, created_at, updated_at, post_tags(id) AS tags, \
+		(SELECT username FROM \"user\" WHERE \"user\".id = post.user_id) AS username";
+}
+
+/// The number of distinct users whose open reports hide a post until it is moderated.
+pub const REPORTS_TO_HIDE: i64 = 5;
+
+/// Why a post was reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "report_reason", rename_all = "snake_case")]
+pub enum ReportReason {
+	/// Unsolicited advertising or repeated content.
+	Spam,
+	/// Bullying or threats against a person.
+	Harassment,
+	/// Attacks on people based on who they are.
+	Hate,
+	/// Violent or graphic content.
+	Violence,
+	/// Sexual content.
+	Sexual,
+	/// False or misleading information.
+	Misinformation,
+	/// Anything else, which should be explained in the `details`.
+	Other,
+}
+
+impl PgHasArrayType for ReportReason {
+	fn array_type_info() -> PgTypeInfo {
+		PgTypeInfo::with_name("_report_reason")
+	}
+}
+
+/// Whether a report was handled by a moderator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "report_status", rename_all = "snake_case")]
+pub enum ReportStatus {
+	/// Waiting for a moderator.
+	Open,
+	/// A moderator acted on the report.
+	Resolved,
+	/// A moderator found nothing wrong with the post.
+	Dismissed,
+}
+
+/// A report of a post that breaks the rules. Each user has at most one report per post.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Report {
+	/// The unique identifier of the report.
+	pub id: Uuid,
+	/// The post that was reported.
+	pub post_id: Uuid,
+	/// The user that reported the post.
+	pub reporter_id: Uuid,
+	/// Why the post was reported.
+	pub reason: ReportReason,
+	/// More details about the report, which may be empty.
+	pub details: String,
+	/// Whether the report was handled by a moderator.
+	pub status: ReportStatus,
+	/// When the report was created.
+	pub created_at: DateTime<Utc>,
+}
+
+impl Report {
+	/// The columns selected for a [`Report`] in dynamic queries.
+	pub const SELECT: &'static str =
+		"SELECT id, post_id, reporter_id, reason, details, status, created_at";
+}
+
+impl Keyset for Report {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.created_at)
+	}
+}
+
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+pub struct ReportInput {
+	/// Why the post breaks the rules.
+	pub reason: ReportReason,
+	/// More details for the moderators, such as what is wrong with the post.
+	#[serde(default)]
+	#[validate(length(max = 1000))]
+	pub details: String,
+}
+
+impl Keyset for Bookmark {
+	fn id(&self) -> Uuid {
+		self.post.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.bookmarked_at)
+	}
+}
+
+impl Keyset for SearchResult {
+	fn id(&self) -> Uuid {
+		self.post.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Number(self.rank.into())
+	}
+}
+
+/// The most operations in a single bulk request.
+pub const MAX_BULK_OPERATIONS: u64 = 100;
+
+/// The largest body of a bulk request, which fits the most operations with plenty of tags.
+pub const MAX_BULK_SIZE: usize = 256 * 1024;
+
+/// An operation on one of your posts, as part of a bulk request.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+	/// Deletes the post.
+	Delete { id: Uuid },
+	/// Publishes the post now, unless it is already published.
+	Publish { id: Uuid },
+	/// Replaces the tags on the post.
+	Retag { id: Uuid, tags: Vec<String> },
+}
+
+impl BulkOperation {
+	pub fn id(&self) -> Uuid {
+		match self {
+			Self::Delete { id } | Self::Publish { id } | Self::Retag { id, .. } => *id,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+#[validate(schema(function = "validate_operations"))]
+pub struct BulkInput {
+	/// The operations to run, in order.
+	#[validate(length(min = 1, max = "MAX_BULK_OPERATIONS"))]
+	pub operations: Vec<BulkOperation>,
+}
+
+fn validate_operations(input: &BulkInput) -> Result<(), ValidationError> {
+	input
+		.operations
+		.iter()
+		.try_for_each(|operation| match operation {
+			BulkOperation::Retag { tags, .. } if tags.len() > 10 => {
+				Err(ValidationError::new("too_many_tags"))
+			}
+			BulkOperation::Retag { tags, .. } => validate_tags(tags),
+			BulkOperation::Delete { .. } | BulkOperation::Publish { .. } => Ok(()),
+		})
+}
+
+/// The result of an operation in a bulk request.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BulkResult {
+	/// The post that the operation was on.
+	pub id: Uuid,
+	/// Whether the operation succeeded. A failed operation leaves the post unchanged.
+	pub ok: bool,
+	/// The post after the operation, unless it failed or deleted the post.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub post: Option<Post>,
+	/// Why the operation failed.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<error::Message<'static>>,
+}
+
+#[cfg(test)]
+mod test {
+	use super::{diff_words, next_slug, slugify, Change, ChangeOp, SearchInput};
+
+	#[test]
+	fn test_diff_words() {
+		let change = |op, text: &str| Change {
+			op,
+			text: text.into(),
+		};
+
+		assert_eq!(
+			diff_words("the quick brown fox", "the slow brown fox jumps"),
+			[
+				change(ChangeOp::Equal, "the "),
+				change(ChangeOp::Delete, "quick"),
+				change(ChangeOp::Insert, "slow"),
+				change(ChangeOp::Equal, " brown fox"),
+				change(ChangeOp::Insert, " jumps"),
+			]
+		);
+		assert_eq!(diff_words("", ""), []);
+	}
+
+	#[test]
+	fn test_slugify() {
+		assert_eq!(slugify("Hello, World!"), "hello-world");
+		assert_eq!(
+			slugify("  Rust 2024 -- what's new?"),
+			"rust-2024-what-s-new"
+		);
+		assert_eq!(slugify("日本語"), "post");
+		assert_eq!(slugify(&"a ".repeat(100)).len(), 55);
+	}
+
+	#[test]
+	fn test_next_slug() {
+		let taken = ["hello".to_string(), "hello-2".to_string()];
+
+		assert_eq!(next_slug("world", &taken), "world");
+		assert_eq!(next_slug("hello", &taken), "hello-3");
+		assert_eq!(next_slug("hello", &taken[1..]), "hello");
+	}
+
+	#[test]
+	fn test_search_to_tsquery() {
+		let search = |q: &str| SearchInput { q: q.into() }.to_tsquery();
+
+		assert_eq!(search("rust axum"), "rust:* & axum:*");
+		assert_eq!(
+			search("\"web framework\" rust"),
+			"(web <-> framework) & rust:*"
+		);
+		assert_eq!(
+			search("it's 'quoted' & !weird"),
+			"it:* & s:* & quoted:* & weird:*"
+		);
+		assert_eq!(search("\"\" \"unclosed"), "(unclosed)");
+	}
 }
@@ -1,159 +1,1265 @@
-use axum::extract::State;
+use std::sync::Arc;
+
+use axum::{
+	extract::State,
+	response::{IntoResponse, Redirect, Response},
+};
+use chrono::{DateTime, Utc};
 use macros::route;
+use sqlx::{Connection, PgConnection, QueryBuilder};
+use uuid::Uuid;
 
 use crate::{
-	extract::{Json, Path, Query, Session},
+	error::ErrorShape,
+	extract::{
+		IfMatch, Json, MaybeSession, Page, Pagination, PatchRequest, Path, Query, Session, Tagged,
+	},
+	markdown,
 	openapi::tag,
+	patch::Patch,
+	route::{model::Sort, tag::model::normalize_all},
+	syndication::{self, Atom, Entry, Feed, Rss},
 	Database,
 };
 
-use super::{model, Error, RouteError};
+use super::{
+	filter::{self, Draft},
+	model, Error, RouteError,
+};
+
+/// Replaces the tags on a post with the given (normalised) names,
+/// creating any tags that do not exist yet.
+async fn set_tags(
+	conn: &mut PgConnection,
+	post_id: Uuid,
+	tags: &[String],
+) -> Result<(), sqlx::Error> {
+	sqlx::query!(
+		r#"
+			INSERT INTO tag (name) SELECT * FROM UNNEST($1::text[])
+			ON CONFLICT (name) DO NOTHING
+		"#,
+		tags,
+	)
+	.execute(&mut *conn)
+	.await?;
+
+	sqlx::query!("DELETE FROM post_tag WHERE post_id = $1", post_id)
+		.execute(&mut *conn)
+		.await?;
+
+	sqlx::query!(
+		r#"
+			INSERT INTO post_tag (post_id, tag_id)
+			SELECT $1, id FROM tag WHERE name = ANY($2)
+		"#,
+		post_id,
+		tags,
+	)
+	.execute(&mut *conn)
+	.await?;
+
+	Ok(())
+}
+
+/// Generates a slug from `title` that is not used by any other post of the
+/// author, including as a previous slug.
+async fn unique_slug(
+	conn: &mut PgConnection,
+	user_id: Uuid,
+	post_id: Option<Uuid>,
+	title: &str,
+) -> Result<String, sqlx::Error> {
+	let slug = model::slugify(title);
+	let taken = sqlx::query_scalar!(
+		r#"
+			SELECT slug AS "slug!" FROM post
+			WHERE user_id = $1 AND id IS DISTINCT FROM $2 AND (slug = $3 OR slug LIKE $3 || '-%')
+			UNION
+			SELECT slug FROM post_slug
+			WHERE user_id = $1 AND post_id IS DISTINCT FROM $2 AND (slug = $3 OR slug LIKE $3 || '-%')
+		"#,
+		user_id,
+		post_id,
+		slug,
+	)
+	.fetch_all(&mut *conn)
+	.await?;
+
+	Ok(model::next_slug(&slug, &taken))
+}
+
+/// Locks a post of the user for an update or deletion, which only
+/// goes ahead if the post still matches the `If-Match` header.
+async fn fetch_for_update(
+	conn: &mut PgConnection,
+	user_id: Uuid,
+	post_id: Uuid,
+	if_match: &IfMatch,
+) -> Result<model::Post, RouteError> {
+	let post = sqlx::query_as!(
+		model::Post,
+		r#"
+			SELECT
+				id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: model::PostStatus", visibility AS "visibility: model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: model::Reactions",
+				post_reacted(id, $2) AS "reacted!: Vec<model::ReactionKind>",
+				post_bookmarked(id, $2) AS "bookmarked!",
+				created_at, updated_at, post_tags(id) AS "tags!"
+			FROM post
+			WHERE id = $1 AND user_id = $2
+			FOR UPDATE
+		"#,
+		post_id,
+		user_id,
+	)
+	.fetch_optional(&mut *conn)
+	.await?
+	.ok_or(Error::UnknownPost(post_id))?;
+
+	if_match.check(&post.etag())?;
+
+	Ok(post)
+}
+
+/// Makes the previous slug of a post redirect to it after its slug changed.
+async fn redirect_slug(
+	conn: &mut PgConnection,
+	post: &model::Post,
+	previous: &str,
+) -> Result<(), RouteError> {
+	// The new slug may have been a previous slug too
+	sqlx::query!(
+		r#"
+			INSERT INTO post_slug (user_id, slug, post_id) VALUES ($1, $2, $3)
+			ON CONFLICT (user_id, slug) DO NOTHING
+		"#,
+		post.user_id,
+		previous,
+		post.id,
+	)
+	.execute(&mut *conn)
+	.await?;
+
+	sqlx::query!(
+		"DELETE FROM post_slug WHERE user_id = $1 AND slug = $2",
+		post.user_id,
+		post.slug,
+	)
+	.execute(&mut *conn)
+	.await?;
+
+	Ok(())
+}
+
+/// Updates a post fetched with [`fetch_for_update`], creating a new revision of it
+/// if its title or content changed.
+async fn update(
+	conn: &mut PgConnection,
+	filters: &filter::Chain,
+	previous: model::Post,
+	input: model::UpdatePost,
+) -> Result<model::Post, RouteError> {
+	let status = input.status.unwrap_or(previous.status);
+	let visibility = input.visibility.unwrap_or(previous.visibility);
+	let published_at = input.published_at.apply(previous.published_at);
+
+	// Only changes to the schedule are checked, so a post that is due can still be edited
+	if !input.status.is_missing() || !input.published_at.is_missing() {
+		check_publish_time(Some(status), published_at)?;
+	}
+
+	if !input.title.is_missing() || !input.content.is_missing() {
+		let draft = Draft {
+			user_id: previous.user_id,
+			post_id: Some(previous.id),
+			title: input.title.as_value().unwrap_or(&previous.title),
+			content: input.content.as_value().unwrap_or(&previous.content),
+		};
+
+		filters.check(&mut *conn, &draft).await?;
+	}
+
+	// Only the title and content are kept in revisions, so other changes don't create one
+	let title = input.title.as_value().unwrap_or(&previous.title);
+	let content = input.content.as_value().unwrap_or(&previous.content);
+	let revised = *title != previous.title || *content != previous.content;
+
+	let slug = match input.title.as_value() {
+		Some(title) => {
+			Some(unique_slug(&mut *conn, previous.user_id, Some(previous.id), title).await?)
+		}
+		None => None,
+	};
+
+	let mut post = sqlx::query_as!(
+		model::Post,
+		r#"
+			UPDATE post
+			SET
+				title = COALESCE($1, title),
+				slug = COALESCE($2, slug),
+				content = COALESCE($3, content),
+				status = $4,
+				revision = CASE WHEN $8 THEN revision + 1 ELSE revision END,
+				published_at = CASE $4::post_status
+					WHEN 'draft' THEN NULL
+					WHEN 'published' THEN COALESCE($5, now())
+					ELSE $5
+				END,
+				visibility = $7
+			WHERE id = $6
+			RETURNING
+				id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: model::PostStatus", visibility AS "visibility: model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: model::Reactions",
+				post_reacted(id, user_id) AS "reacted!: Vec<model::ReactionKind>",
+				post_bookmarked(id, user_id) AS "bookmarked!",
+				created_at, updated_at, post_tags(id) AS "tags!"
+		"#,
+		input.title.value(),
+		slug,
+		input.content.value(),
+		status as model::PostStatus,
+		published_at,
+		previous.id,
+		visibility as model::Visibility,
+		revised,
+	)
+	.fetch_one(&mut *conn)
+	.await?;
+
+	if post.slug != previous.slug {
+		redirect_slug(&mut *conn, &post, &previous.slug).await?;
+	}
+
+	if revised {
+		sqlx::query!(
+			r#"
+				INSERT INTO post_revision (post_id, number, title, content)
+				VALUES ($1, $2, $3, $4)
+			"#,
+			post.id,
+			post.revision,
+			post.title,
+			post.content,
+		)
+		.execute(&mut *conn)
+		.await?;
+	}
+
+	if let Some(tags) = input.tags.value() {
+		post.tags = normalize_all(&tags);
+		set_tags(&mut *conn, post.id, &post.tags).await?;
+	}
+
+	Ok(post)
+}
+
+/// Runs an operation of a bulk request on a post of the user, returning the post
+/// afterwards, or `None` if it was deleted.
+async fn apply_operation(
+	conn: &mut PgConnection,
+	filters: &filter::Chain,
+	user_id: Uuid,
+	operation: model::BulkOperation,
+) -> Result<Option<model::Post>, RouteError> {
+	let previous =
+		fetch_for_update(&mut *conn, user_id, operation.id(), &IfMatch::default()).await?;
+	let mut input = model::UpdatePost {
+		title: Patch::Missing,
+		content: Patch::Missing,
+		tags: Patch::Missing,
+		status: Patch::Missing,
+		visibility: Patch::Missing,
+		published_at: Patch::Missing,
+	};
+
+	match operation {
+		model::BulkOperation::Delete { id } => {
+			sqlx::query!("DELETE FROM post WHERE id = $1", id)
+				.execute(&mut *conn)
+				.await?;
+
+			return Ok(None);
+		}
+		model::BulkOperation::Publish { .. } if previous.status == model::PostStatus::Published => {
+			return Ok(Some(previous));
+		}
+		// Scheduled posts are published now rather than at their scheduled time
+		model::BulkOperation::Publish { .. } => {
+			input.status = Patch::Value(model::PostStatus::Published);
+			input.published_at = Patch::Null;
+		}
+		model::BulkOperation::Retag { tags, .. } => input.tags = Patch::Value(tags),
+	}
+
+	Ok(Some(update(conn, filters, previous, input).await?))
+}
+
+/// Fetches a post by its id, if it is visible to the viewer.
+async fn fetch_post(
+	database: &Database,
+	post_id: Uuid,
+	viewer: Option<Uuid>,
+) -> Result<model::Post, RouteError> {
+	let post = sqlx::query_as!(
+		model::Post,
+		r#"
+			SELECT
+				id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: model::PostStatus", visibility AS "visibility: model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: model::Reactions",
+				post_reacted(id, $2) AS "reacted!: Vec<model::ReactionKind>",
+				post_bookmarked(id, $2) AS "bookmarked!",
+				created_at, updated_at, post_tags(id) AS "tags!"
+			FROM post
+			WHERE id = $1 AND post_readable(user_id, status, visibility, hidden, $2)
+		"#,
+		post_id,
+		viewer,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownPost(post_id))?;
+
+	Ok(post)
+}
+
+/// Fills in the reactions that the viewer left on each of the posts, and whether
+/// they bookmarked them, for lists that cannot select them along with the posts.
+pub async fn set_viewer_state<'p>(
+	database: &Database,
+	viewer: Option<Uuid>,
+	posts: impl IntoIterator<Item = &'p mut model::Post>,
+) -> Result<(), sqlx::Error> {
+	let Some(viewer) = viewer else {
+		return Ok(());
+	};
+
+	let mut posts = posts.into_iter().collect::<Vec<_>>();
+	let ids = posts.iter().map(|post| post.id).collect::<Vec<_>>();
+	let reactions = sqlx::query!(
+		r#"
+			SELECT post_id, kind AS "kind: model::ReactionKind" FROM reaction
+			WHERE user_id = $1 AND post_id = ANY($2)
+			ORDER BY kind
+		"#,
+		viewer,
+		&ids,
+	)
+	.fetch_all(database)
+	.await?;
+
+	for reaction in reactions {
+		if let Some(post) = posts.iter_mut().find(|post| post.id == reaction.post_id) {
+			post.reacted.push(reaction.kind);
+		}
+	}
+
+	let bookmarks = sqlx::query_scalar!(
+		"SELECT post_id FROM bookmark WHERE user_id = $1 AND post_id = ANY($2)",
+		viewer,
+		&ids,
+	)
+	.fetch_all(database)
+	.await?;
+
+	for post in posts {
+		post.bookmarked = bookmarks.contains(&post.id);
+	}
+
+	Ok(())
+}
+
+/// Fills in the content of each of the posts rendered as HTML. The output is
+/// cached per revision, so each revision is only rendered the first time.
+pub async fn render_html<'p>(
+	database: &Database,
+	posts: impl IntoIterator<Item = &'p mut model::Post>,
+) -> Result<(), sqlx::Error> {
+	let mut posts = posts.into_iter().collect::<Vec<_>>();
+	let ids = posts.iter().map(|post| post.id).collect::<Vec<_>>();
+	let revisions = posts.iter().map(|post| post.revision).collect::<Vec<_>>();
+	let cached = sqlx::query!(
+		r#"
+			SELECT post_id, content_html AS "content_html!" FROM post_revision
+			WHERE (post_id, number) IN (SELECT * FROM UNNEST($1::uuid[], $2::int[]))
+				AND content_html IS NOT NULL
+		"#,
+		&ids,
+		&revisions,
+	)
+	.fetch_all(database)
+	.await?;
+
+	for row in cached {
+		if let Some(post) = posts.iter_mut().find(|post| post.id == row.post_id) {
+			post.content_html = Some(row.content_html);
+		}
+	}
+
+	let rendered = posts
+		.iter_mut()
+		.filter(|post| post.content_html.is_none())
+		.map(|post| {
+			let html = markdown::render(&post.content);
+
+			post.content_html = Some(html.clone());
+			(post.id, post.revision, html)
+		})
+		.collect::<Vec<_>>();
+
+	if rendered.is_empty() {
+		return Ok(());
+	}
+
+	let (ids, (revisions, html)): (Vec<_>, (Vec<_>, Vec<_>)) = rendered
+		.into_iter()
+		.map(|(id, revision, html)| (id, (revision, html)))
+		.unzip();
+
+	sqlx::query!(
+		r#"
+			UPDATE post_revision SET content_html = rendered.html
+			FROM UNNEST($1::uuid[], $2::int[], $3::text[]) AS rendered (post_id, number, html)
+			WHERE post_revision.post_id = rendered.post_id AND post_revision.number = rendered.number
+		"#,
+		&ids,
+		&revisions,
+		&html,
+	)
+	.execute(database)
+	.await?;
+
+	Ok(())
+}
+
+/// The number of posts in an Atom or RSS feed.
+const FEED_SIZE: i64 = 20;
+
+/// Builds a feed of the newest posts that are listed publicly, or only those of
+/// one author if their id and username are given. `path` is the path of the feed itself.
+pub async fn syndicate(
+	database: &Database,
+	author: Option<(Uuid, &str)>,
+	path: &str,
+) -> Result<Feed, sqlx::Error> {
+	let mut query = QueryBuilder::new(model::SyndicatedPost::SELECT);
+
+	query.push(" FROM post WHERE status = 'published' AND ");
+	model::push_listed(&mut query, None);
+
+	if let Some((user_id, _)) = author {
+		query.push(" AND user_id = ").push_bind(user_id);
+	}
+
+	query
+		.push(" ORDER BY published_at DESC, id DESC LIMIT ")
+		.push_bind(FEED_SIZE);
+
+	let mut posts = query
+		.build_query_as::<model::SyndicatedPost>()
+		.fetch_all(database)
+		.await?;
+
+	render_html(database, posts.iter_mut().map(|post| &mut post.post)).await?;
+
+	// An empty feed that never had posts stays at the Unix epoch until one is published
+	let updated = sqlx::query_scalar!(
+		"SELECT MAX(changed_at) FROM post_change WHERE $1::uuid IS NULL OR user_id = $1",
+		author.map(|(user_id, _)| user_id),
+	)
+	.fetch_one(database)
+	.await?
+	.unwrap_or(DateTime::UNIX_EPOCH);
+
+	let (title, description, link) = match author {
+		Some((_, username)) => (
+			format!("Posts by {username}"),
+			format!("The newest published posts by {username}."),
+			syndication::url(&format!("/posts?author={username}")),
+		),
+		None => (
+			"Posts".into(),
+			"The newest published posts.".into(),
+			syndication::url("/posts"),
+		),
+	};
+
+	Ok(Feed {
+		title,
+		description,
+		url: syndication::url(path),
+		link,
+		updated,
+		entries: posts
+			.into_iter()
+			.map(|model::SyndicatedPost { post, username }| Entry {
+				id: post.id,
+				link: syndication::url(&format!("/users/{username}/posts/{}", post.slug)),
+				title: post.title,
+				author: username,
+				content_html: post.content_html.unwrap_or_default(),
+				categories: post.tags,
+				published: post.published_at.unwrap_or(post.created_at),
+				updated: post.updated_at,
+			})
+			.collect(),
+	})
+}
+
+/// Returns whether the viewer is the author of a post, or an error
+/// if the post does not exist or is not visible to them.
+async fn is_author(
+	database: &Database,
+	post_id: Uuid,
+	viewer: Option<Uuid>,
+) -> Result<bool, RouteError> {
+	let author = sqlx::query_scalar!(
+		r#"
+			SELECT user_id = $2 IS TRUE AS "author!" FROM post
+			WHERE id = $1 AND post_readable(user_id, status, visibility, hidden, $2)
+		"#,
+		post_id,
+		viewer,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownPost(post_id))?;
+
+	Ok(author)
+}
+
+/// Fetches a revision of a post. Only the author can see revisions other than the current one.
+async fn fetch_revision(
+	database: &Database,
+	post_id: Uuid,
+	number: i32,
+	author: bool,
+) -> Result<model::Revision, RouteError> {
+	let revision = sqlx::query_as!(
+		model::Revision,
+		r#"
+			SELECT post_revision.id, number, post_revision.title, post_revision.content, post_revision.created_at
+			FROM post_revision
+			JOIN post ON post.id = post_revision.post_id
+			WHERE post_id = $1 AND number = $2 AND ($3 OR number = post.revision)
+		"#,
+		post_id,
+		number,
+		author,
+	)
+	.fetch_optional(database)
+	.await?
+	.ok_or(Error::UnknownRevision(number))?;
+
+	Ok(revision)
+}
+
+/// Ensures that a scheduled post is published in the future.
+fn check_publish_time(
+	status: Option<model::PostStatus>,
+	published_at: Option<chrono::DateTime<Utc>>,
+) -> Result<(), Error> {
+	match (status, published_at) {
+		(Some(model::PostStatus::Scheduled), Some(at)) if at > Utc::now() => Ok(()),
+		(Some(model::PostStatus::Scheduled), _) => Err(Error::InvalidPublishTime),
+		_ => Ok(()),
+	}
+}
 
 /// Get own posts
 /// Returns a paginated response of your posts, newest first.
+/// Posts of every status are included unless filtered by `status`.
 #[route(tag = tag::POST)]
 pub async fn get_user_posts(
 	State(database): State<Database>,
 	session: Session,
-	Query(paginate): Query<model::Paginate>,
-) -> Result<Json<Vec<model::Post>>, RouteError> {
-	let posts = sqlx::query_as!(
-		model::Post,
-		r#"
-			SELECT * FROM post
-			WHERE user_id = $1
-			ORDER BY created_at DESC
-			LIMIT $2 OFFSET $3
-		"#,
-		session.user.id,
-		paginate.limit(),
-		paginate.offset(),
-	)
-	.fetch_all(&database)
-	.await?;
+	Query(filter): Query<model::StatusInput>,
+	Query(since): Query<model::UpdatedSinceInput>,
+	Query(render): Query<model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Post>, RouteError> {
+	let mut posts = pagination
+		.fetch(&database, model::Post::SELECT, Sort::NEWEST, |query| {
+			query
+				.push(" FROM post WHERE user_id = ")
+				.push_bind(session.user.id);
+
+			if let Some(status) = filter.status {
+				query.push(" AND status = ").push_bind(status);
+			}
 
-	Ok(Json(posts))
+			since.push_filter(query);
+		})
+		.await?;
+
+	set_viewer_state(&database, Some(session.user.id), &mut posts.items).await?;
+
+	if render.html() {
+		render_html(&database, &mut posts.items).await?;
+	}
+
+	Ok(posts)
 }
 
 /// Get all posts
-/// Returns a paginated response of all posts, newest first.
-#[route(tag = tag::POST)]
+/// Returns a paginated response of all posts, newest first unless sorted otherwise.
+/// Posts can be filtered by author, tag, creation time and the time they last changed.
+/// Unpublished, unlisted and hidden posts are only included for their author.
+#[route(
+	tag = tag::POST,
+	response(status = 304, description = "The page has not changed since it was fetched.")
+)]
 pub async fn get_posts(
 	State(database): State<Database>,
-	Query(paginate): Query<model::Paginate>,
-) -> Result<Json<Vec<model::Post>>, RouteError> {
-	let posts = sqlx::query_as!(
-		model::Post,
-		r#"
-			SELECT * FROM post
-			ORDER BY created_at DESC
-			LIMIT $1 OFFSET $2
-		"#,
-		paginate.limit(),
-		paginate.offset(),
+	session: MaybeSession,
+	Query(filter): Query<model::FilterInput>,
+	Query(since): Query<model::UpdatedSinceInput>,
+	Query(render): Query<model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Post>, RouteError> {
+	let viewer = session.user_id();
+	let mut posts = pagination
+		.fetch(
+			&database,
+			model::Post::SELECT,
+			filter.sort.into(),
+			|query| {
+				query.push(" FROM post WHERE ");
+				model::push_listed(query, viewer);
+				filter.push_filters(query);
+				since.push_filter(query);
+			},
+		)
+		.await?;
+
+	set_viewer_state(&database, viewer, &mut posts.items).await?;
+
+	if render.html() {
+		render_html(&database, &mut posts.items).await?;
+	}
+
+	Ok(posts)
+}
+
+/// Search posts
+/// Returns a paginated response of published posts matching a full-text search query, best matches first.
+/// Unlisted posts are left out, along with posts that are hidden from you.
+#[route(tag = tag::POST)]
+pub async fn search_posts(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Query(search): Query<model::SearchInput>,
+	Query(render): Query<model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<model::SearchResult>, RouteError> {
+	let tsquery = search.to_tsquery();
+	let viewer = session.user_id();
+	let mut posts = pagination
+		.fetch(
+			&database,
+			model::SearchResult::SELECT,
+			Sort::descending("rank"),
+			|query| {
+				query
+					.push(" FROM (SELECT post.*, query, ts_rank(search, query) AS rank")
+					.push(" FROM post, to_tsquery('english', ")
+					.push_bind(tsquery.clone())
+					.push(") query WHERE search @@ query) post WHERE status = 'published' AND ");
+				model::push_listed(query, viewer);
+			},
+		)
+		.await?;
+
+	set_viewer_state(
+		&database,
+		viewer,
+		posts
+			.items
+			.iter_mut()
+			.map(|result: &mut model::SearchResult| &mut result.post),
 	)
-	.fetch_all(&database)
 	.await?;
 
-	Ok(Json(posts))
+	if render.html() {
+		render_html(
+			&database,
+			posts.items.iter_mut().map(|result| &mut result.post),
+		)
+		.await?;
+	}
+
+	Ok(posts)
+}
+
+/// Get Atom feed
+/// Returns the newest public posts as an Atom 1.0 feed, with their content rendered as HTML.
+#[route(
+	tag = tag::POST,
+	response(status = 304, description = "The feed has not changed since it was fetched.")
+)]
+pub async fn get_atom_feed(State(database): State<Database>) -> Result<Atom, RouteError> {
+	let feed = syndicate(&database, None, "/posts/feed.atom").await?;
+
+	Ok(Atom(feed))
+}
+
+/// Get RSS feed
+/// Returns the newest public posts as an RSS 2.0 feed, with their content rendered as HTML.
+#[route(
+	tag = tag::POST,
+	response(status = 304, description = "The feed has not changed since it was fetched.")
+)]
+pub async fn get_rss_feed(State(database): State<Database>) -> Result<Rss, RouteError> {
+	let feed = syndicate(&database, None, "/posts/feed.rss").await?;
+
+	Ok(Rss(feed))
 }
 
 /// Get single post
-/// Returns a single post by its unique id.
-#[route(tag = tag::POST)]
+/// Returns a single post by its unique id or slug. Unpublished posts are only visible to their author,
+/// and published posts depend on their `visibility`. Hidden posts are not found.
+///
+/// When several authors have a post with the same slug, your own post is returned,
+/// or else the earliest published one. Previous slugs redirect to the post by its id.
+#[route(
+	tag = tag::POST,
+	response(status = 200, shape = "Tagged<Json<model::Post>>"),
+	response(status = 304, description = "The post has not changed since it was fetched."),
+	response(status = 308, description = "The slug has changed, redirects to the post by its id.")
+)]
 pub async fn get_post(
 	State(database): State<Database>,
-	Path(path): Path<model::IdInput>,
-) -> Result<Json<model::Post>, RouteError> {
-	let post = sqlx::query_as!(
+	session: MaybeSession,
+	Path(path): Path<model::KeyInput>,
+	Query(render): Query<model::RenderInput>,
+) -> Result<Response, RouteError> {
+	let viewer = session.user_id();
+
+	if let Ok(id) = Uuid::parse_str(&path.id) {
+		let mut post = fetch_post(&database, id, viewer).await?;
+
+		if render.html() {
+			render_html(&database, [&mut post]).await?;
+		}
+
+		return Ok(post.tagged().into_response());
+	}
+
+	let mut post = sqlx::query_as!(
 		model::Post,
 		r#"
-			SELECT * FROM post
-			WHERE id = $1
+			SELECT
+				id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: model::PostStatus", visibility AS "visibility: model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: model::Reactions",
+				post_reacted(id, $2) AS "reacted!: Vec<model::ReactionKind>",
+				post_bookmarked(id, $2) AS "bookmarked!",
+				created_at, updated_at, post_tags(id) AS "tags!"
+			FROM post
+			WHERE
+				post_readable(user_id, status, visibility, hidden, $2)
+				AND (slug = $1 OR id IN (SELECT post_id FROM post_slug WHERE slug = $1))
+			ORDER BY slug = $1 DESC, user_id = $2 IS TRUE DESC, published_at, created_at
+			LIMIT 1
 		"#,
 		path.id,
+		viewer,
 	)
 	.fetch_optional(&database)
-	.await?;
+	.await?
+	.ok_or(Error::UnknownSlug(path.id.clone()))?;
+
+	if post.slug != path.id {
+		let mut location = format!("/posts/{}", post.id);
 
-	Ok(Json(post.ok_or(Error::UnknownPost(path.id))?))
+		if render.html() {
+			location.push_str("?render=html");
+		}
+
+		return Ok(Redirect::permanent(&location).into_response());
+	}
+
+	if render.html() {
+		render_html(&database, [&mut post]).await?;
+	}
+
+	Ok(post.tagged().into_response())
 }
 
 /// Create post
 /// Creates a new post.
-#[route(tag = tag::POST)]
+#[route(
+	tag = tag::POST,
+	response(status = 422, description = "The post was rejected by a filter."),
+	response(status = 429, description = "Too many posts were created in the last hour.")
+)]
 pub async fn create_post(
 	State(database): State<Database>,
+	State(filters): State<Arc<filter::Chain>>,
 	session: Session,
 	Json(input): Json<model::CreatePost>,
-) -> Result<Json<model::Post>, RouteError> {
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	check_publish_time(Some(input.status), input.published_at)?;
+
+	let tags = normalize_all(&input.tags);
+	let mut tx = database.begin().await?;
+	let draft = Draft {
+		user_id: session.user.id,
+		post_id: None,
+		title: &input.title,
+		content: &input.content,
+	};
+
+	filters.check(&mut tx, &draft).await?;
+	let slug = unique_slug(&mut tx, session.user.id, None, &input.title).await?;
+
 	let post = sqlx::query_as!(
 		model::Post,
 		r#"
-			INSERT INTO post (id, user_id, title, content)
-			VALUES (DEFAULT, $1, $2, $3)
-			RETURNING *
+			INSERT INTO post (id, user_id, title, slug, content, status, published_at, visibility)
+			VALUES (
+				DEFAULT, $1, $2, $3, $4, $5::post_status,
+				CASE $5::post_status
+					WHEN 'draft' THEN NULL
+					WHEN 'published' THEN COALESCE($6, now())
+					ELSE $6
+				END,
+				$8
+			)
+			RETURNING
+				id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: model::PostStatus", visibility AS "visibility: model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: model::Reactions",
+				'{}'::reaction_kind[] AS "reacted!: Vec<model::ReactionKind>",
+				FALSE AS "bookmarked!",
+				created_at, updated_at, $7::text[] AS "tags!"
 		"#,
 		session.user.id,
 		input.title,
+		slug,
 		input.content,
+		input.status as model::PostStatus,
+		input.published_at,
+		&tags,
+		input.visibility as model::Visibility,
 	)
-	.fetch_one(&database)
+	.fetch_one(&mut *tx)
 	.await?;
 
-	Ok(Json(post))
+	sqlx::query!(
+		r#"
+			INSERT INTO post_revision (post_id, number, title, content, created_at)
+			VALUES ($1, 1, $2, $3, $4)
+		"#,
+		post.id,
+		post.title,
+		post.content,
+		post.created_at,
+	)
+	.execute(&mut *tx)
+	.await?;
+
+	set_tags(&mut tx, post.id, &tags).await?;
+	tx.commit().await?;
+
+	Ok(post.tagged())
 }
 
 /// Update post
-/// Updates an existing post by its unique id.
-#[route(tag = tag::POST)]
+/// Updates an existing post by its unique id. Changing the title generates a new slug,
+/// and the previous one redirects to the post. Every update creates a new revision.
+///
+/// Send the `ETag` of the post in `If-Match` to avoid overwriting changes made since you fetched it.
+#[route(
+	tag = tag::POST,
+	response(status = 412, description = "The post has changed since it was fetched."),
+	response(status = 422, description = "The post was rejected by a filter.")
+)]
 pub async fn update_post(
 	State(database): State<Database>,
+	State(filters): State<Arc<filter::Chain>>,
 	session: Session,
+	if_match: IfMatch,
 	Path(path): Path<model::IdInput>,
 	Json(input): Json<model::UpdatePost>,
-) -> Result<Json<model::Post>, RouteError> {
-	let post = sqlx::query_as!(
-		model::Post,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	let mut tx = database.begin().await?;
+	let previous = fetch_for_update(&mut tx, session.user.id, path.id, &if_match).await?;
+	let post = update(&mut tx, &filters, previous, input).await?;
+
+	tx.commit().await?;
+
+	Ok(post.tagged())
+}
+
+/// Patch post
+/// Partially updates an existing post by its unique id, with either a JSON Merge Patch
+/// (`application/merge-patch+json`) or a JSON Patch (`application/json-patch+json`).
+///
+/// In a merge patch, missing fields are left unchanged and `null` clears a field, such as
+/// `published_at`. A JSON Patch is applied to the post as returned by `GET /posts/{id}`.
+#[route(
+	tag = tag::POST,
+	response(status = 412, description = "The post has changed since it was fetched."),
+	response(status = 415, description = "The request body is not a supported patch format."),
+	response(status = 422, description = "The JSON Patch could not be applied to the post, or the post was rejected by a filter.")
+)]
+pub async fn patch_post(
+	State(database): State<Database>,
+	State(filters): State<Arc<filter::Chain>>,
+	session: Session,
+	if_match: IfMatch,
+	Path(path): Path<model::IdInput>,
+	request: PatchRequest<model::UpdatePost>,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	let mut tx = database.begin().await?;
+	let previous = fetch_for_update(&mut tx, session.user.id, path.id, &if_match).await?;
+	let input = request.resolve(&previous).map_err(RouteError::App)?;
+	let post = update(&mut tx, &filters, previous, input).await?;
+
+	tx.commit().await?;
+
+	Ok(post.tagged())
+}
+
+/// Delete post
+/// Deletes an existing post by its unique id.
+///
+/// Send the `ETag` of the post in `If-Match` to avoid deleting changes made since you fetched it.
+#[route(
+	tag = tag::POST,
+	response(status = 412, description = "The post has changed since it was fetched.")
+)]
+pub async fn delete_post(
+	State(database): State<Database>,
+	session: Session,
+	if_match: IfMatch,
+	Path(path): Path<model::IdInput>,
+) -> Result<(), RouteError> {
+	let mut tx = database.begin().await?;
+
+	fetch_for_update(&mut tx, session.user.id, path.id, &if_match).await?;
+
+	sqlx::query!("DELETE FROM post WHERE id = $1", path.id)
+		.execute(&mut *tx)
+		.await?;
+
+	tx.commit().await?;
+
+	Ok(())
+}
+
+/// Bulk update posts
+/// Deletes, publishes or retags several of your posts at once, in a single transaction.
+/// Each operation succeeds or fails on its own, and the results are in the same order
+/// as the operations. Up to 100 operations can be sent at once.
+#[route(
+	tag = tag::POST,
+	response(status = 413, description = "The request body is too large.")
+)]
+pub async fn bulk_update_posts(
+	State(database): State<Database>,
+	State(filters): State<Arc<filter::Chain>>,
+	session: Session,
+	Json(input): Json<model::BulkInput>,
+) -> Result<Json<Vec<model::BulkResult>>, RouteError> {
+	let mut tx = database.begin().await?;
+	let mut results = Vec::with_capacity(input.operations.len());
+
+	for operation in input.operations {
+		let id = operation.id();
+		// Each operation runs in a savepoint, so a failed one is undone without the others
+		let mut savepoint = tx.begin().await?;
+		let result =
+			match apply_operation(&mut savepoint, &filters, session.user.id, operation).await {
+				Ok(post) => {
+					savepoint.commit().await?;
+					model::BulkResult {
+						id,
+						ok: true,
+						post,
+						error: None,
+					}
+				}
+				Err(RouteError::Route(error)) => {
+					savepoint.rollback().await?;
+					model::BulkResult {
+						id,
+						ok: false,
+						post: None,
+						error: ErrorShape::into_errors(error).into_iter().next(),
+					}
+				}
+				Err(error) => return Err(error),
+			};
+
+		results.push(result);
+	}
+
+	tx.commit().await?;
+
+	Ok(Json(results))
+}
+
+/// Get post revisions
+/// Returns a paginated response of the revisions of a post, newest first.
+/// Only the author can see revisions other than the current one.
+#[route(tag = tag::POST)]
+pub async fn get_revisions(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::IdInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Revision>, RouteError> {
+	let author = is_author(&database, path.id, session.user_id()).await?;
+	let revisions = pagination
+		.fetch(
+			&database,
+			model::Revision::SELECT,
+			Sort::descending("number"),
+			|query| {
+				query
+					.push(" FROM post_revision WHERE post_id = ")
+					.push_bind(path.id);
+
+				if !author {
+					query.push(" AND number = (SELECT revision FROM post WHERE id = post_id)");
+				}
+			},
+		)
+		.await?;
+
+	Ok(revisions)
+}
+
+/// Get post revision
+/// Returns a single revision of a post by its number.
+/// Only the author can see revisions other than the current one.
+#[route(tag = tag::POST)]
+pub async fn get_revision(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::RevisionInput>,
+) -> Result<Json<model::Revision>, RouteError> {
+	let author = is_author(&database, path.id, session.user_id()).await?;
+	let revision = fetch_revision(&database, path.id, path.rev, author).await?;
+
+	Ok(Json(revision))
+}
+
+/// Diff post revisions
+/// Returns the word-level differences between the title and content of two revisions of a post.
+/// Only the author can see revisions other than the current one.
+#[route(tag = tag::POST)]
+pub async fn diff_revisions(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::IdInput>,
+	Query(input): Query<model::DiffInput>,
+) -> Result<Json<model::Diff>, RouteError> {
+	let author = is_author(&database, path.id, session.user_id()).await?;
+	let (from, to) = tokio::try_join!(
+		fetch_revision(&database, path.id, input.from, author),
+		fetch_revision(&database, path.id, input.to, author),
+	)?;
+
+	Ok(Json(model::Diff::new(&from, &to)))
+}
+
+/// Restore post revision
+/// Restores the title and content of a post to those of a previous revision.
+/// This creates a new revision, leaving the history unchanged.
+#[route(
+	tag = tag::POST,
+	response(status = 412, description = "The post has changed since it was fetched.")
+)]
+pub async fn restore_revision(
+	State(database): State<Database>,
+	State(filters): State<Arc<filter::Chain>>,
+	session: Session,
+	if_match: IfMatch,
+	Path(path): Path<model::RevisionInput>,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	let author = is_author(&database, path.id, Some(session.user.id)).await?;
+
+	if !author {
+		return Err(Error::UnknownPost(path.id).into());
+	}
+
+	let revision = fetch_revision(&database, path.id, path.rev, author).await?;
+	let input = model::UpdatePost {
+		title: Patch::Value(revision.title),
+		content: Patch::Value(revision.content),
+		tags: Patch::Missing,
+		status: Patch::Missing,
+		visibility: Patch::Missing,
+		published_at: Patch::Missing,
+	};
+
+	let mut tx = database.begin().await?;
+	let previous = fetch_for_update(&mut tx, session.user.id, path.id, &if_match).await?;
+	let post = update(&mut tx, &filters, previous, input).await?;
+
+	tx.commit().await?;
+
+	Ok(post.tagged())
+}
+
+/// React to post
+/// Leaves a reaction on a post. Each user can leave one reaction of each kind on a post,
+/// so reacting again with the same kind has no effect.
+#[route(tag = tag::POST)]
+pub async fn add_reaction(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::ReactionInput>,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	is_author(&database, path.id, Some(session.user.id)).await?;
+
+	sqlx::query!(
 		r#"
-			UPDATE post
-			SET title = COALESCE($1, title), content = COALESCE($2, content)
-			WHERE id = $3 AND user_id = $4
-			RETURNING *
+			INSERT INTO reaction (post_id, user_id, kind) VALUES ($1, $2, $3)
+			ON CONFLICT (post_id, user_id, kind) DO NOTHING
 		"#,
-		input.title,
-		input.content,
 		path.id,
 		session.user.id,
+		path.kind as model::ReactionKind,
 	)
-	.fetch_optional(&database)
+	.execute(&database)
 	.await?;
 
-	Ok(Json(post.ok_or(Error::UnknownPost(path.id))?))
+	let post = fetch_post(&database, path.id, Some(session.user.id)).await?;
+
+	Ok(post.tagged())
 }
 
-/// Delete post
-/// Deletes an existing post by its unique id.
+/// Remove reaction
+/// Removes a reaction of a kind that you left on a post, if there is one.
 #[route(tag = tag::POST)]
-pub async fn delete_post(
+pub async fn remove_reaction(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::ReactionInput>,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	is_author(&database, path.id, Some(session.user.id)).await?;
+
+	sqlx::query!(
+		"DELETE FROM reaction WHERE post_id = $1 AND user_id = $2 AND kind = $3",
+		path.id,
+		session.user.id,
+		path.kind as model::ReactionKind,
+	)
+	.execute(&database)
+	.await?;
+
+	let post = fetch_post(&database, path.id, Some(session.user.id)).await?;
+
+	Ok(post.tagged())
+}
+
+/// Bookmark post
+/// Saves a post to your bookmarks. Bookmarking a post again has no effect.
+#[route(tag = tag::POST)]
+pub async fn add_bookmark(
 	State(database): State<Database>,
 	session: Session,
 	Path(path): Path<model::IdInput>,
-) -> Result<(), RouteError> {
-	let post = sqlx::query!(
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	is_author(&database, path.id, Some(session.user.id)).await?;
+
+	sqlx::query!(
 		r#"
-			DELETE FROM post
-			WHERE id = $1 AND user_id = $2
+			INSERT INTO bookmark (user_id, post_id) VALUES ($1, $2)
+			ON CONFLICT (user_id, post_id) DO NOTHING
 		"#,
+		session.user.id,
 		path.id,
+	)
+	.execute(&database)
+	.await?;
+
+	let post = fetch_post(&database, path.id, Some(session.user.id)).await?;
+
+	Ok(post.tagged())
+}
+
+/// Remove bookmark
+/// Removes a post from your bookmarks, if it is bookmarked.
+#[route(tag = tag::POST)]
+pub async fn remove_bookmark(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::IdInput>,
+) -> Result<Tagged<Json<model::Post>>, RouteError> {
+	is_author(&database, path.id, Some(session.user.id)).await?;
+
+	sqlx::query!(
+		"DELETE FROM bookmark WHERE user_id = $1 AND post_id = $2",
 		session.user.id,
+		path.id,
 	)
 	.execute(&database)
 	.await?;
 
-	if post.rows_affected() == 0 {
-		return Err(Error::UnknownPost(path.id).into());
+	let post = fetch_post(&database, path.id, Some(session.user.id)).await?;
+
+	Ok(post.tagged())
+}
+
+/// Report post
+/// Reports a post that breaks the rules to the moderators. Reporting a post again replaces
+/// the reason and details of your report, which still only counts once.
+///
+/// Once enough users report a post, it is hidden until a moderator looks at it.
+#[route(tag = tag::POST)]
+pub async fn report_post(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::IdInput>,
+	Json(input): Json<model::ReportInput>,
+) -> Result<Json<model::Report>, RouteError> {
+	if is_author(&database, path.id, Some(session.user.id)).await? {
+		return Err(Error::ReportOwnPost.into());
 	}
 
-	Ok(())
+	let report = sqlx::query_as!(
+		model::Report,
+		r#"
+			INSERT INTO report (post_id, reporter_id, reason, details) VALUES ($1, $2, $3, $4)
+			ON CONFLICT (post_id, reporter_id) DO UPDATE
+			SET reason = EXCLUDED.reason, details = EXCLUDED.details
+			RETURNING
+				id, post_id, reporter_id, reason AS "reason: model::ReportReason", details,
+				status AS "status: model::ReportStatus", created_at
+		"#,
+		path.id,
+		session.user.id,
+		input.reason as model::ReportReason,
+		input.details,
+	)
+	.fetch_one(&database)
+	.await?;
+
+	sqlx::query!(
+		r#"
+			UPDATE post SET hidden = TRUE
+			WHERE id = $1 AND NOT hidden
+				AND (SELECT COUNT(*) FROM report WHERE post_id = $1 AND status = 'open') >= $2
+		"#,
+		path.id,
+		model::REPORTS_TO_HIDE,
+	)
+	.execute(&database)
+	.await?;
+
+	Ok(Json(report))
 }
@@ -0,0 +1,144 @@
+use aide::axum::{routing::get_with, ApiRouter};
+use axum::http::StatusCode;
+
+use crate::{error, AppState};
+
+pub mod model;
+pub mod route;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+	#[error("tag_not_found")]
+	UnknownTag(String),
+}
+
+type RouteError = error::RouteError<Error>;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new()
+		.api_route("/", get_with(list_tags, list_tags_docs))
+		.api_route("/:name/posts", get_with(get_tag_posts, get_tag_posts_docs))
+}
+
+impl error::ErrorShape for Error {
+	fn status(&self) -> StatusCode {
+		match self {
+			Self::UnknownTag(..) => StatusCode::NOT_FOUND,
+		}
+	}
+
+	fn into_errors(self) -> Vec<error::Message<'static>> {
+		let message = match self {
+			Self::UnknownTag(..) => "The tag you provided does not exist.",
+		};
+
+		let message = error::Message::new(self.to_string()).content(message);
+		let Self::UnknownTag(name) = self;
+
+		message.detail("name", name).into_vec()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_tags(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let post = app
+			.post("/posts")
+			.json(&json!({
+				"title": "first",
+				"content": "",
+				"tags": ["Rust", "web development", "rust"],
+			}))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["tags"], json!(["rust", "web-development"]));
+
+		app.post("/posts")
+			.json(&json!({ "title": "second", "content": "", "tags": ["rust"] }))
+			.await;
+
+		let response = app
+			.post("/posts")
+			.json(&json!({ "title": "third", "content": "", "tags": ["c++"] }))
+			.await;
+
+		assert_eq!(response.status_code(), 400);
+
+		let page = app.get("/tags").await.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["name"], "rust");
+		assert_eq!(page["items"][0]["post_count"], 2);
+		assert_eq!(page["items"][1]["name"], "web-development");
+		assert_eq!(page["items"][1]["post_count"], 1);
+
+		let page = app
+			.get("/tags/Web%20Development/posts")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+		assert_eq!(page["items"][0]["title"], "first");
+
+		let page = app
+			.get("/posts")
+			.add_query_param("tag", "rust")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 2);
+		assert_eq!(page["items"][0]["tags"], json!(["rust"]));
+
+		let post = app
+			.put(&format!("/posts/{}", post["id"].as_str().unwrap()))
+			.json(&json!({ "tags": [] }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["tags"], json!([]));
+
+		let page = app.get("/tags").await.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+
+		let response = app.get("/tags/unknown/posts").await;
+
+		assert_eq!(response.status_code(), 404);
+
+		// writes return the tags in the same order as reads, so their entity tags still match
+		let response = app
+			.post("/posts")
+			.json(
+				&json!({ "title": "fourth", "content": "", "tags": ["Zoë", "a-b", "ab", "Éclair", "a"] }),
+			)
+			.await;
+		let post = response.json::<serde_json::Value>();
+		let fetched = app
+			.get(&format!("/posts/{}", post["id"].as_str().unwrap()))
+			.await;
+
+		assert_eq!(post["tags"], json!(["a", "a-b", "ab", "zoë", "éclair"]));
+		assert_eq!(fetched.json::<serde_json::Value>()["tags"], post["tags"]);
+		app.put(&format!("/posts/{}", post["id"].as_str().unwrap()))
+			.add_header("if-match".parse().unwrap(), response.header("etag"))
+			.json(&json!({ "title": "fourth" }))
+			.await
+			.assert_status_ok();
+	}
+}
@@ -0,0 +1,100 @@
+pub use crate::route::model::{Keyset, SortKey};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Normalises a tag name: it is trimmed, lowercased, and every run of
+/// whitespace or underscores is replaced with a single hyphen.
+pub fn normalize(name: &str) -> String {
+	name.split(|c: char| c.is_whitespace() || c == '_')
+		.filter(|part| !part.is_empty())
+		.collect::<Vec<_>>()
+		.join("-")
+		.to_lowercase()
+}
+
+/// Normalises a list of tag names, removing any duplicates.
+pub fn normalize_all(names: &[String]) -> Vec<String> {
+	let mut names = names.iter().map(|name| normalize(name)).collect::<Vec<_>>();
+
+	names.sort_unstable();
+	names.dedup();
+	names
+}
+
+pub fn validate_name(name: &str) -> Result<(), ValidationError> {
+	let name = normalize(name);
+
+	if name.is_empty() || name.chars().count() > 32 {
+		return Err(ValidationError::new(
+			"tag must be between 1 and 32 characters",
+		));
+	}
+
+	if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
+		return Err(ValidationError::new("tag must be alphanumeric"));
+	}
+
+	Ok(())
+}
+
+pub fn validate_tags(names: &[String]) -> Result<(), ValidationError> {
+	names.iter().try_for_each(|name| validate_name(name))
+}
+
+/// A tag that is used by at least one post.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Tag {
+	/// The unique identifier of the tag.
+	#[serde(skip)]
+	pub id: Uuid,
+	/// The normalised name of the tag.
+	pub name: String,
+	/// The number of posts with the tag.
+	pub post_count: i64,
+	/// The creation time of the tag, when it was first used.
+	pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Keyset for Tag {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Text(self.name.clone())
+	}
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct NameInput {
+	/// The name of the tag.
+	#[validate(custom(function = "validate_name"))]
+	pub name: String,
+}
+
+#[cfg(test)]
+mod test {
+	use super::{normalize, normalize_all, validate_name};
+
+	#[test]
+	fn test_normalize() {
+		assert_eq!(normalize("  Rust "), "rust");
+		assert_eq!(normalize("Web  Development"), "web-development");
+		assert_eq!(normalize("snake_case"), "snake-case");
+		assert_eq!(
+			normalize_all(&["b".into(), "A".into(), "a".into()]),
+			["a", "b"]
+		);
+	}
+
+	#[test]
+	fn test_validate_name() {
+		assert!(validate_name("Web Development").is_ok());
+		assert!(validate_name("   ").is_err());
+		assert!(validate_name("c++").is_err());
+		assert!(validate_name(&"a".repeat(33)).is_err());
+	}
+}
@@ -0,0 +1,75 @@
+use axum::extract::State;
+use macros::route;
+
+use crate::{
+	extract::{MaybeSession, Page, Pagination, Path, Query},
+	openapi::tag,
+	route::{model::Sort, post},
+	Database,
+};
+
+use super::{model, Error, RouteError};
+
+/// List tags
+/// Returns a paginated response of all tags in use, alphabetically, with the number of public posts for each.
+#[route(tag = tag::TAG)]
+pub async fn list_tags(
+	State(database): State<Database>,
+	pagination: Pagination,
+) -> Result<Page<model::Tag>, RouteError> {
+	let tags = pagination
+		.fetch(
+			&database,
+			"SELECT id, name, created_at, (SELECT COUNT(*) FROM post_tag \
+			JOIN post ON post.id = post_id WHERE tag_id = tag.id AND status = 'published' AND visibility = 'public' AND NOT hidden) \
+			AS post_count",
+			Sort::ascending("name"),
+			|query| {
+				query.push(" FROM tag WHERE EXISTS (SELECT 1 FROM post_tag JOIN post ON post.id = post_id")
+					.push(" WHERE tag_id = tag.id AND status = 'published' AND visibility = 'public' AND NOT hidden)");
+			},
+		)
+		.await?;
+
+	Ok(tags)
+}
+
+/// Get tagged posts
+/// Returns a paginated response of the posts with a tag, newest first.
+#[route(tag = tag::TAG)]
+pub async fn get_tag_posts(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::NameInput>,
+	Query(render): Query<post::model::RenderInput>,
+	pagination: Pagination,
+) -> Result<Page<post::model::Post>, RouteError> {
+	let name = model::normalize(&path.name);
+	let tag = sqlx::query_scalar!("SELECT id FROM tag WHERE name = $1", name)
+		.fetch_optional(&database)
+		.await?
+		.ok_or(Error::UnknownTag(name))?;
+
+	let mut posts = pagination
+		.fetch(
+			&database,
+			post::model::Post::SELECT,
+			Sort::NEWEST,
+			|query| {
+				query
+					.push(" FROM post WHERE id IN (SELECT post_id FROM post_tag WHERE tag_id = ")
+					.push_bind(tag)
+					.push(") AND status = 'published' AND ");
+				post::model::push_listed(query, session.user_id());
+			},
+		)
+		.await?;
+
+	post::route::set_viewer_state(&database, session.user_id(), &mut posts.items).await?;
+
+	if render.html() {
+		post::route::render_html(&database, &mut posts.items).await?;
+	}
+
+	Ok(posts)
+}
@@ -0,0 +1,232 @@
+use aide::axum::{
+	routing::{get_with, post_with},
+	ApiRouter,
+};
+use axum::http::StatusCode;
+
+use crate::{error, AppState};
+
+pub mod model;
+pub mod route;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+	#[error("post_not_found")]
+	UnknownPost(String),
+	#[error("user_not_found")]
+	UnknownUser(String),
+	#[error("cannot_follow_self")]
+	FollowSelf,
+}
+
+type RouteError = error::RouteError<Error>;
+
+pub fn routes() -> ApiRouter<AppState> {
+	use route::*;
+
+	ApiRouter::new()
+		.api_route(
+			"/:username/posts/:slug",
+			get_with(get_user_post, get_user_post_docs),
+		)
+		.api_route(
+			"/:username/feed.atom",
+			get_with(get_user_atom_feed, get_user_atom_feed_docs),
+		)
+		.api_route(
+			"/:username/feed.rss",
+			get_with(get_user_rss_feed, get_user_rss_feed_docs),
+		)
+		.api_route(
+			"/:username/follow",
+			post_with(follow_user, follow_user_docs).delete_with(unfollow_user, unfollow_user_docs),
+		)
+		.api_route(
+			"/:username/followers",
+			get_with(get_followers, get_followers_docs),
+		)
+		.api_route(
+			"/:username/following",
+			get_with(get_following, get_following_docs),
+		)
+}
+
+impl error::ErrorShape for Error {
+	fn status(&self) -> StatusCode {
+		match self {
+			Self::UnknownPost(..) | Self::UnknownUser(..) => StatusCode::NOT_FOUND,
+			Self::FollowSelf => StatusCode::BAD_REQUEST,
+		}
+	}
+
+	fn into_errors(self) -> Vec<error::Message<'static>> {
+		let message = match self {
+			Self::UnknownPost(..) => "The post you provided does not exist.",
+			Self::UnknownUser(..) => "The user you provided does not exist.",
+			Self::FollowSelf => "You cannot follow yourself.",
+		};
+
+		let message = error::Message::new(self.to_string()).content(message);
+
+		match self {
+			Self::UnknownPost(slug) => message.detail("slug", slug),
+			Self::UnknownUser(username) => message.detail("username", username),
+			Self::FollowSelf => message,
+		}
+		.into_vec()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use axum::http::StatusCode;
+
+	use crate::test::*;
+
+	#[sqlx::test]
+	async fn test_post_slugs(pool: Database) {
+		let app = app(pool);
+
+		app.post("/auth/register")
+			.json(&json!({
+				"email": "john@smith.com",
+				"username": "john",
+				"password": "hunter2hunter",
+			}))
+			.await;
+
+		let mut slugs = Vec::new();
+
+		for _ in 0..2 {
+			let post = app
+				.post("/posts")
+				.json(&json!({ "title": "Hello, World!", "content": "" }))
+				.await
+				.json::<serde_json::Value>();
+
+			slugs.push(post["slug"].as_str().unwrap().to_string());
+		}
+
+		assert_eq!(slugs, ["hello-world", "hello-world-2"]);
+
+		let post = app
+			.get("/users/john/posts/hello-world")
+			.await
+			.json::<serde_json::Value>();
+		let id = post["id"].as_str().unwrap();
+
+		let post = app
+			.put(&format!("/posts/{id}"))
+			.json(&json!({ "title": "Goodbye" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["slug"], "goodbye");
+
+		let response = app.get("/users/john/posts/hello-world").await;
+
+		assert_eq!(response.status_code(), StatusCode::PERMANENT_REDIRECT);
+		assert_eq!(response.header("location"), "/users/john/posts/goodbye");
+
+		let response = app.get("/posts/hello-world").await;
+
+		assert_eq!(response.status_code(), StatusCode::PERMANENT_REDIRECT);
+		assert_eq!(response.header("location"), format!("/posts/{id}").as_str());
+
+		let post = app.get("/posts/goodbye").await.json::<serde_json::Value>();
+
+		assert_eq!(post["id"], id);
+
+		// Previous slugs of other posts are not reused
+		let post = app
+			.post("/posts")
+			.json(&json!({ "title": "hello world", "content": "" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["slug"], "hello-world-3");
+
+		// Reverting the title reclaims the previous slug
+		let post = app
+			.put(&format!("/posts/{id}"))
+			.json(&json!({ "title": "Hello world" }))
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(post["slug"], "hello-world");
+		app.get("/users/john/posts/hello-world")
+			.await
+			.assert_status_ok();
+
+		let response = app.get("/users/jane/posts/hello-world").await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"post_not_found"
+		);
+	}
+
+	#[sqlx::test]
+	async fn test_follow(pool: Database) {
+		let other = app(pool.clone());
+		let app = app(pool);
+
+		for (server, name) in [(&app, "john"), (&other, "jane")] {
+			server
+				.post("/auth/register")
+				.json(&json!({
+					"email": format!("{name}@smith.com"),
+					"username": name,
+					"password": "hunter2hunter",
+				}))
+				.await;
+		}
+
+		// following twice has no effect
+		for _ in 0..2 {
+			app.post("/users/jane/follow").await.assert_status_ok();
+		}
+
+		let page = app
+			.get("/users/jane/followers")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 1);
+		assert_eq!(page["items"][0]["username"], "john");
+
+		let page = app
+			.get("/users/john/following")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["items"][0]["username"], "jane");
+
+		let response = app.post("/users/john/follow").await;
+
+		assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"cannot_follow_self"
+		);
+
+		let response = app.post("/users/nobody/follow").await;
+
+		assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+		assert_eq!(
+			response.json::<serde_json::Value>()[0]["code"],
+			"user_not_found"
+		);
+
+		app.delete("/users/jane/follow").await.assert_status_ok();
+
+		let page = other
+			.get("/users/jane/followers")
+			.await
+			.json::<serde_json::Value>();
+
+		assert_eq!(page["total"], 0);
+	}
+}
@@ -0,0 +1,46 @@
+pub use crate::route::model::{Keyset, SortKey};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct SlugInput {
+	/// The username of the author.
+	pub username: String,
+	/// The current or a previous slug of the post.
+	pub slug: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct UsernameInput {
+	/// The username of the user.
+	pub username: String,
+}
+
+/// A user in a list of followers or followed users.
+#[derive(Debug, Serialize, JsonSchema, sqlx::FromRow)]
+pub struct Follow {
+	/// The unique identifier of the user.
+	pub id: Uuid,
+	/// The username of the user.
+	pub username: String,
+	/// When the user was followed.
+	pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Follow {
+	/// The columns selected for a [`Follow`] in dynamic queries.
+	pub const SELECT: &'static str = "SELECT id, username, created_at";
+}
+
+impl Keyset for Follow {
+	fn id(&self) -> Uuid {
+		self.id
+	}
+
+	fn key(&self, _column: &str) -> SortKey {
+		SortKey::Timestamp(self.created_at)
+	}
+}
@@ -0,0 +1,224 @@
+use axum::{
+	extract::State,
+	response::{IntoResponse, Redirect, Response},
+};
+use macros::route;
+use uuid::Uuid;
+
+use crate::{
+	extract::{Json, MaybeSession, Page, Pagination, Path, Query, Session, Tagged},
+	openapi::tag,
+	route::{model::Sort, post},
+	syndication::{Atom, Rss},
+	Database,
+};
+
+use super::{model, Error, RouteError};
+
+/// Returns the id of the user with a username.
+async fn fetch_user_id(database: &Database, username: &str) -> Result<Uuid, RouteError> {
+	let id = sqlx::query_scalar!(r#"SELECT id FROM "user" WHERE username = $1"#, username)
+		.fetch_optional(database)
+		.await?
+		.ok_or_else(|| Error::UnknownUser(username.into()))?;
+
+	Ok(id)
+}
+
+/// Get post by slug
+/// Returns a single post of a user by its slug. Unpublished posts are only visible to their author,
+/// and published posts depend on their `visibility`.
+///
+/// Previous slugs of a post redirect to its current slug.
+#[route(
+	tag = tag::USER,
+	response(status = 200, shape = "Tagged<Json<post::model::Post>>"),
+	response(status = 308, description = "The slug has changed, redirects to the current slug.")
+)]
+pub async fn get_user_post(
+	State(database): State<Database>,
+	session: MaybeSession,
+	Path(path): Path<model::SlugInput>,
+	Query(render): Query<post::model::RenderInput>,
+) -> Result<Response, RouteError> {
+	let mut post = sqlx::query_as!(
+		post::model::Post,
+		r#"
+			SELECT
+				post.id, user_id, title, slug, content, NULL::text AS "content_html",
+				status AS "status: post::model::PostStatus",
+				visibility AS "visibility: post::model::Visibility",
+				hidden, published_at, revision, comment_count,
+				reactions AS "reactions: post::model::Reactions",
+				post_reacted(post.id, $3) AS "reacted!: Vec<post::model::ReactionKind>",
+				post_bookmarked(post.id, $3) AS "bookmarked!",
+				post.created_at, post.updated_at, post_tags(post.id) AS "tags!"
+			FROM post
+			JOIN "user" ON "user".id = post.user_id
+			WHERE
+				"user".username = $1
+				AND post_readable(user_id, status, visibility, hidden, $3)
+				AND (
+					slug = $2
+					OR post.id IN (SELECT post_id FROM post_slug WHERE user_id = "user".id AND slug = $2)
+				)
+		"#,
+		path.username,
+		path.slug,
+		session.user_id(),
+	)
+	.fetch_optional(&database)
+	.await?
+	.ok_or(Error::UnknownPost(path.slug.clone()))?;
+
+	if post.slug != path.slug {
+		let mut location = format!("/users/{}/posts/{}", path.username, post.slug);
+
+		if render.html() {
+			location.push_str("?render=html");
+		}
+
+		return Ok(Redirect::permanent(&location).into_response());
+	}
+
+	if render.html() {
+		post::route::render_html(&database, [&mut post]).await?;
+	}
+
+	Ok(post.tagged().into_response())
+}
+
+/// Get user Atom feed
+/// Returns the newest public posts of a user as an Atom 1.0 feed, with their content rendered as HTML.
+#[route(
+	tag = tag::USER,
+	response(status = 304, description = "The feed has not changed since it was fetched.")
+)]
+pub async fn get_user_atom_feed(
+	State(database): State<Database>,
+	Path(path): Path<model::UsernameInput>,
+) -> Result<Atom, RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+	let feed = post::route::syndicate(
+		&database,
+		Some((user_id, &path.username)),
+		&format!("/users/{}/feed.atom", path.username),
+	)
+	.await?;
+
+	Ok(Atom(feed))
+}
+
+/// Get user RSS feed
+/// Returns the newest public posts of a user as an RSS 2.0 feed, with their content rendered as HTML.
+#[route(
+	tag = tag::USER,
+	response(status = 304, description = "The feed has not changed since it was fetched.")
+)]
+pub async fn get_user_rss_feed(
+	State(database): State<Database>,
+	Path(path): Path<model::UsernameInput>,
+) -> Result<Rss, RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+	let feed = post::route::syndicate(
+		&database,
+		Some((user_id, &path.username)),
+		&format!("/users/{}/feed.rss", path.username),
+	)
+	.await?;
+
+	Ok(Rss(feed))
+}
+
+/// Follow user
+/// Follows a user, adding their posts to your feed. Following a user again has no effect.
+#[route(tag = tag::USER)]
+pub async fn follow_user(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::UsernameInput>,
+) -> Result<(), RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+
+	if user_id == session.user.id {
+		return Err(Error::FollowSelf.into());
+	}
+
+	sqlx::query!(
+		r#"
+			INSERT INTO follow (follower_id, followee_id) VALUES ($1, $2)
+			ON CONFLICT (follower_id, followee_id) DO NOTHING
+		"#,
+		session.user.id,
+		user_id,
+	)
+	.execute(&database)
+	.await?;
+
+	Ok(())
+}
+
+/// Unfollow user
+/// Stops following a user, removing their posts from your feed.
+#[route(tag = tag::USER)]
+pub async fn unfollow_user(
+	State(database): State<Database>,
+	session: Session,
+	Path(path): Path<model::UsernameInput>,
+) -> Result<(), RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+
+	sqlx::query!(
+		"DELETE FROM follow WHERE follower_id = $1 AND followee_id = $2",
+		session.user.id,
+		user_id,
+	)
+	.execute(&database)
+	.await?;
+
+	Ok(())
+}
+
+/// Get followers
+/// Returns a paginated response of the users that follow a user, most recently followed first.
+#[route(tag = tag::USER)]
+pub async fn get_followers(
+	State(database): State<Database>,
+	Path(path): Path<model::UsernameInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Follow>, RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+	let followers = pagination
+		.fetch(&database, model::Follow::SELECT, Sort::NEWEST, |query| {
+			query
+				.push(r#" FROM (SELECT "user".id, username, follow.created_at FROM follow"#)
+				.push(r#" JOIN "user" ON "user".id = follower_id WHERE followee_id = "#)
+				.push_bind(user_id)
+				.push(") follow WHERE TRUE");
+		})
+		.await?;
+
+	Ok(followers)
+}
+
+/// Get followed users
+/// Returns a paginated response of the users that a user follows, most recently followed first.
+#[route(tag = tag::USER)]
+pub async fn get_following(
+	State(database): State<Database>,
+	Path(path): Path<model::UsernameInput>,
+	pagination: Pagination,
+) -> Result<Page<model::Follow>, RouteError> {
+	let user_id = fetch_user_id(&database, &path.username).await?;
+	let following = pagination
+		.fetch(&database, model::Follow::SELECT, Sort::NEWEST, |query| {
+			query
+				.push(r#" FROM (SELECT "user".id, username, follow.created_at FROM follow"#)
+				.push(r#" JOIN "user" ON "user".id = followee_id WHERE follower_id = "#)
+				.push_bind(user_id)
+				.push(") follow WHERE TRUE");
+		})
+		.await?;
+
+	Ok(following)
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use crate::Database;
+
+/// How often scheduled posts are checked for publication.
+const INTERVAL: Duration = Duration::from_secs(15);
+
+/// Publishes every scheduled post whose `published_at` has passed,
+/// returning the number of posts that were published.
+pub async fn publish_scheduled_posts(database: &Database) -> Result<u64, sqlx::Error> {
+	let result = sqlx::query!(
+		r#"
+			UPDATE post
+			SET status = 'published'
+			WHERE status = 'scheduled' AND published_at <= now()
+		"#
+	)
+	.execute(database)
+	.await?;
+
+	Ok(result.rows_affected())
+}
+
+/// Publishes scheduled posts in the background every [`INTERVAL`].
+pub fn spawn(database: Database) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(INTERVAL);
+
+		loop {
+			interval.tick().await;
+
+			match publish_scheduled_posts(&database).await {
+				Ok(0) => {}
+				Ok(count) => tracing::info!("published {count} scheduled post(s)"),
+				Err(error) => tracing::error!("failed to publish scheduled posts: {error}"),
+			}
+		}
+	});
+}
@@ -0,0 +1,96 @@
+use std::{
+	io::{ErrorKind, SeekFrom},
+	ops::Range,
+	path::PathBuf,
+};
+
+use bytes::Bytes;
+use tokio::{
+	fs,
+	io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use super::{Error, Storage};
+
+/// Stores objects as files in a directory, which is created when needed.
+#[derive(Debug)]
+pub struct LocalStorage {
+	root: PathBuf,
+}
+
+impl LocalStorage {
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self { root: root.into() }
+	}
+
+	/// Returns the path of the file for `key`. Keys are generated by the
+	/// application, but are checked anyway so they cannot escape the directory.
+	fn path(&self, key: &str) -> Result<PathBuf, Error> {
+		if key.is_empty() || key.starts_with('.') || key.contains(['/', '\\']) {
+			return Err(Error::NotFound);
+		}
+
+		Ok(self.root.join(key))
+	}
+}
+
+fn not_found(error: std::io::Error) -> Error {
+	if error.kind() == ErrorKind::NotFound {
+		Error::NotFound
+	} else {
+		Error::Io(error)
+	}
+}
+
+#[axum::async_trait]
+impl Storage for LocalStorage {
+	async fn put(&self, key: &str, bytes: Bytes) -> Result<(), Error> {
+		let path = self.path(key)?;
+		// Written to a temporary file first, so a partial file is never read
+		let temporary = self.root.join(format!(".{key}.tmp"));
+
+		fs::create_dir_all(&self.root).await?;
+		fs::write(&temporary, &bytes).await?;
+		fs::rename(&temporary, &path).await?;
+
+		Ok(())
+	}
+
+	async fn get(&self, key: &str, range: Option<Range<usize>>) -> Result<Bytes, Error> {
+		let path = self.path(key)?;
+
+		let Some(range) = range else {
+			return fs::read(&path).await.map(Bytes::from).map_err(not_found);
+		};
+
+		let mut file = fs::File::open(&path).await.map_err(not_found)?;
+		let mut bytes = vec![0; range.len()];
+
+		file.seek(SeekFrom::Start(range.start as u64)).await?;
+		file.read_exact(&mut bytes).await?;
+
+		Ok(bytes.into())
+	}
+
+	async fn delete(&self, key: &str) -> Result<(), Error> {
+		match fs::remove_file(self.path(key)?).await.map_err(not_found) {
+			Ok(()) | Err(Error::NotFound) => Ok(()),
+			Err(error) => Err(error),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::LocalStorage;
+	use crate::storage::test::check_storage;
+
+	#[tokio::test]
+	async fn test_local_storage() {
+		let root = std::env::temp_dir().join(format!("storage-{}", uuid::Uuid::new_v4()));
+
+		check_storage(&LocalStorage::new(&root)).await;
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+}
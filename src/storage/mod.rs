@@ -0,0 +1,78 @@
+//! Storage for uploaded files.
+//!
+//! Files are stored as objects under a key, in a [`Storage`] backend that is
+//! chosen with the `STORAGE` environment variable: either a directory on the
+//! local filesystem, or an `s3://<bucket>` URL for an S3-compatible object store.
+
+use std::{ops::Range, sync::Arc};
+
+use bytes::Bytes;
+
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("object not found")]
+	NotFound,
+	#[error("io error: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("object store error: {0}")]
+	ObjectStore(#[from] object_store::Error),
+}
+
+/// A backend that stores files as objects under a key.
+#[axum::async_trait]
+pub trait Storage: Send + Sync {
+	/// Stores `bytes` under `key`, replacing any existing object.
+	async fn put(&self, key: &str, bytes: Bytes) -> Result<(), Error>;
+
+	/// Reads the object under `key`, or only the bytes in `range` if one is given.
+	/// The range must be within the object.
+	async fn get(&self, key: &str, range: Option<Range<usize>>) -> Result<Bytes, Error>;
+
+	/// Deletes the object under `key`. Deleting a missing object is not an error.
+	async fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+/// Creates the backend described by `url`: an `s3://<bucket>` URL, or else a local directory.
+///
+/// The S3 backend is configured with the usual `AWS_*` environment variables, such as
+/// `AWS_ENDPOINT` to use an S3-compatible service other than AWS.
+pub fn from_url(url: &str) -> Result<Arc<dyn Storage>, Error> {
+	Ok(match url.strip_prefix("s3://") {
+		Some(bucket) => Arc::new(S3Storage::from_env(bucket)?),
+		None => Arc::new(LocalStorage::new(url)),
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use bytes::Bytes;
+
+	use super::{Error, Storage};
+
+	/// Runs the same checks against any backend.
+	pub async fn check_storage(storage: &dyn Storage) {
+		let key = uuid::Uuid::new_v4().to_string();
+
+		storage
+			.put(&key, Bytes::from_static(b"hello world"))
+			.await
+			.unwrap();
+
+		assert_eq!(storage.get(&key, None).await.unwrap(), "hello world");
+		assert_eq!(storage.get(&key, Some(6..11)).await.unwrap(), "world");
+
+		storage.delete(&key).await.unwrap();
+		storage.delete(&key).await.unwrap();
+
+		assert!(matches!(
+			storage.get(&key, None).await,
+			Err(Error::NotFound)
+		));
+	}
+}
@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+use bytes::Bytes;
+use object_store::{
+	aws::{AmazonS3, AmazonS3Builder},
+	path::Path,
+	ObjectStore, PutPayload,
+};
+
+use super::{Error, Storage};
+
+/// Stores objects in a bucket of Amazon S3 or an S3-compatible service, such as `MinIO`.
+#[derive(Debug)]
+pub struct S3Storage {
+	store: AmazonS3,
+}
+
+impl S3Storage {
+	/// Connects to `bucket` with the configuration in the `AWS_*` environment variables.
+	pub fn from_env(bucket: &str) -> Result<Self, Error> {
+		let store = AmazonS3Builder::from_env()
+			.with_bucket_name(bucket)
+			.build()?;
+
+		Ok(Self { store })
+	}
+}
+
+fn not_found(error: object_store::Error) -> Error {
+	match error {
+		object_store::Error::NotFound { .. } => Error::NotFound,
+		error => Error::ObjectStore(error),
+	}
+}
+
+#[axum::async_trait]
+impl Storage for S3Storage {
+	async fn put(&self, key: &str, bytes: Bytes) -> Result<(), Error> {
+		self.store
+			.put(&Path::from(key), PutPayload::from(bytes))
+			.await?;
+
+		Ok(())
+	}
+
+	async fn get(&self, key: &str, range: Option<Range<usize>>) -> Result<Bytes, Error> {
+		let path = Path::from(key);
+
+		match range {
+			Some(range) => self.store.get_range(&path, range).await,
+			None => match self.store.get(&path).await {
+				Ok(result) => result.bytes().await,
+				Err(error) => Err(error),
+			},
+		}
+		.map_err(not_found)
+	}
+
+	async fn delete(&self, key: &str) -> Result<(), Error> {
+		match self.store.delete(&Path::from(key)).await.map_err(not_found) {
+			Ok(()) | Err(Error::NotFound) => Ok(()),
+			Err(error) => Err(error),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::S3Storage;
+	use crate::storage::test::check_storage;
+
+	/// Runs against the `minio` service in `compose.yml`, with
+	/// `docker compose --profile dev up minio`.
+	#[tokio::test]
+	#[ignore = "requires the S3 stand-in from compose.yml"]
+	async fn test_s3_storage() {
+		for (key, value) in [
+			("AWS_ENDPOINT", "http://localhost:9000"),
+			("AWS_ALLOW_HTTP", "true"),
+			("AWS_ACCESS_KEY_ID", "minioadmin"),
+			("AWS_SECRET_ACCESS_KEY", "minioadmin"),
+			("AWS_REGION", "us-east-1"),
+		] {
+			if std::env::var_os(key).is_none() {
+				std::env::set_var(key, value);
+			}
+		}
+
+		check_storage(&S3Storage::from_env("attachments").unwrap()).await;
+	}
+}
@@ -0,0 +1,344 @@
+//! Atom and RSS feeds of posts, for feed readers.
+//!
+//! Feeds are written by hand instead of with an XML library, since they only
+//! need a few elements. Every value is escaped with [`escape`], and links are
+//! absolute URLs under `PUBLIC_URL`, as feed readers require.
+
+use std::fmt::Write;
+
+use aide::{
+	openapi::{MediaType, SchemaObject},
+	OperationOutput,
+};
+use axum::{
+	http::{header, HeaderValue},
+	response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{conditional::http_date, extract::ETag};
+
+pub const ATOM_TYPE: &str = "application/atom+xml; charset=utf-8";
+pub const RSS_TYPE: &str = "application/rss+xml; charset=utf-8";
+
+/// Returns the absolute URL of a path, such as `/posts`.
+pub fn url(path: &str) -> String {
+	format!("{}{path}", crate::env!("PUBLIC_URL").trim_end_matches('/'))
+}
+
+/// Escapes text for use in XML content and attribute values.
+pub fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			// Control characters other than whitespace are not allowed in XML 1.0
+			c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+			c => escaped.push(c),
+		}
+	}
+
+	escaped
+}
+
+/// A feed of the newest entries, newest first.
+#[derive(Debug)]
+pub struct Feed {
+	pub title: String,
+	pub description: String,
+	/// The URL of the feed itself.
+	pub url: String,
+	/// The URL of the same entries in the API.
+	pub link: String,
+	/// The last time that a post in the feed could have changed, including posts that are
+	/// no longer in it, so that it never goes back when a post is unpublished or deleted.
+	pub updated: DateTime<Utc>,
+	pub entries: Vec<Entry>,
+}
+
+/// A single post in a [`Feed`].
+#[derive(Debug)]
+pub struct Entry {
+	pub id: Uuid,
+	pub title: String,
+	pub link: String,
+	pub author: String,
+	/// The content of the post rendered as HTML.
+	pub content_html: String,
+	pub categories: Vec<String>,
+	pub published: DateTime<Utc>,
+	pub updated: DateTime<Utc>,
+}
+
+impl Entry {
+	/// A permanent identifier of the entry, which does not change with its slug.
+	fn urn(&self) -> String {
+		format!("urn:uuid:{}", self.id)
+	}
+}
+
+impl Feed {
+	/// Writes the feed as an Atom 1.0 document (RFC 4287).
+	pub fn atom(&self) -> String {
+		let mut xml = String::new();
+
+		xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+		xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+		let _ = write!(
+			xml,
+			r#"<id>{url}</id><title>{title}</title><subtitle>{description}</subtitle><updated>{updated}</updated><link rel="self" type="application/atom+xml" href="{url}"/><link rel="alternate" type="application/json" href="{link}"/>"#,
+			url = escape(&self.url),
+			title = escape(&self.title),
+			description = escape(&self.description),
+			updated = self.updated.to_rfc3339(),
+			link = escape(&self.link),
+		);
+
+		for entry in &self.entries {
+			let _ = write!(
+				xml,
+				r#"<entry><id>{id}</id><title type="text">{title}</title><link rel="alternate" type="application/json" href="{link}"/><author><name>{author}</name></author><published>{published}</published><updated>{updated}</updated>"#,
+				id = entry.urn(),
+				title = escape(&entry.title),
+				link = escape(&entry.link),
+				author = escape(&entry.author),
+				published = entry.published.to_rfc3339(),
+				updated = entry.updated.to_rfc3339(),
+			);
+
+			for category in &entry.categories {
+				let _ = write!(xml, r#"<category term="{}"/>"#, escape(category));
+			}
+
+			let _ = write!(
+				xml,
+				r#"<content type="html">{}</content></entry>"#,
+				escape(&entry.content_html)
+			);
+		}
+
+		xml.push_str("</feed>");
+		xml
+	}
+
+	/// Writes the feed as an RSS 2.0 document.
+	///
+	/// RSS only allows email addresses in `<author>`, so the
+	/// username of the author is in `<dc:creator>` instead.
+	pub fn rss(&self) -> String {
+		let mut xml = String::new();
+
+		xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+		xml.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/">"#);
+		let _ = write!(
+			xml,
+			r#"<channel><title>{title}</title><link>{link}</link><description>{description}</description><lastBuildDate>{updated}</lastBuildDate><atom:link rel="self" type="application/rss+xml" href="{url}"/>"#,
+			title = escape(&self.title),
+			link = escape(&self.link),
+			description = escape(&self.description),
+			updated = self.updated.to_rfc2822(),
+			url = escape(&self.url),
+		);
+
+		for entry in &self.entries {
+			let _ = write!(
+				xml,
+				r#"<item><title>{title}</title><link>{link}</link><guid isPermaLink="false">{id}</guid><dc:creator>{author}</dc:creator><pubDate>{published}</pubDate>"#,
+				title = escape(&entry.title),
+				link = escape(&entry.link),
+				id = entry.urn(),
+				author = escape(&entry.author),
+				published = entry.published.to_rfc2822(),
+			);
+
+			for category in &entry.categories {
+				let _ = write!(xml, "<category>{}</category>", escape(category));
+			}
+
+			let _ = write!(
+				xml,
+				"<description>{}</description></item>",
+				escape(&entry.content_html)
+			);
+		}
+
+		xml.push_str("</channel></rss>");
+		xml
+	}
+}
+
+/// Responds with the body and content type of a feed, tagged with a hash
+/// of the body and the time it was last updated for conditional requests.
+fn respond(body: String, content_type: &'static str, updated: DateTime<Utc>) -> Response {
+	let etag = ETag::hash(body.as_bytes());
+	let mut response = body.into_response();
+	let headers = response.headers_mut();
+
+	headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+	headers.insert(header::LAST_MODIFIED, http_date(updated));
+
+	if let Ok(etag) = HeaderValue::from_str(etag.as_str()) {
+		headers.insert(header::ETAG, etag);
+	}
+
+	response
+}
+
+/// Documents a `200 OK` response with an XML document of the `media_type`.
+fn document(media_type: &str, description: &str) -> aide::openapi::Response {
+	let schema = schemars::schema::SchemaObject {
+		instance_type: Some(schemars::schema::InstanceType::String.into()),
+		..Default::default()
+	};
+
+	aide::openapi::Response {
+		description: description.into(),
+		content: [(
+			media_type.into(),
+			MediaType {
+				schema: Some(SchemaObject {
+					json_schema: schema.into(),
+					example: None,
+					external_docs: None,
+				}),
+				..Default::default()
+			},
+		)]
+		.into_iter()
+		.collect(),
+		..Default::default()
+	}
+}
+
+/// Responds with a [`Feed`] as an Atom document.
+pub struct Atom(pub Feed);
+
+impl IntoResponse for Atom {
+	fn into_response(self) -> Response {
+		respond(self.0.atom(), ATOM_TYPE, self.0.updated)
+	}
+}
+
+impl OperationOutput for Atom {
+	type Inner = String;
+
+	fn operation_response(
+		_ctx: &mut aide::gen::GenContext,
+		_operation: &mut aide::openapi::Operation,
+	) -> Option<aide::openapi::Response> {
+		Some(document("application/atom+xml", "An Atom 1.0 feed."))
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|response| vec![(Some(200), response)])
+			.unwrap_or_default()
+	}
+}
+
+/// Responds with a [`Feed`] as an RSS document.
+pub struct Rss(pub Feed);
+
+impl IntoResponse for Rss {
+	fn into_response(self) -> Response {
+		respond(self.0.rss(), RSS_TYPE, self.0.updated)
+	}
+}
+
+impl OperationOutput for Rss {
+	type Inner = String;
+
+	fn operation_response(
+		_ctx: &mut aide::gen::GenContext,
+		_operation: &mut aide::openapi::Operation,
+	) -> Option<aide::openapi::Response> {
+		Some(document("application/rss+xml", "An RSS 2.0 feed."))
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::gen::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|response| vec![(Some(200), response)])
+			.unwrap_or_default()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use chrono::{DateTime, TimeZone, Utc};
+	use uuid::Uuid;
+
+	use super::{escape, Entry, Feed};
+
+	fn feed() -> Feed {
+		let time = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+
+		Feed {
+			title: "Posts & more".into(),
+			description: "The newest posts.".into(),
+			url: "http://localhost/posts/feed.atom".into(),
+			link: "http://localhost/posts".into(),
+			updated: time,
+			entries: vec![Entry {
+				id: Uuid::nil(),
+				title: "<Hello>".into(),
+				link: "http://localhost/users/a/posts/hello".into(),
+				author: "a".into(),
+				content_html: "<p>Hi</p>".into(),
+				categories: vec!["rust".into()],
+				published: time,
+				updated: time,
+			}],
+		}
+	}
+
+	#[test]
+	fn test_escape() {
+		assert_eq!(
+			escape("<a href=\"x\">'&'</a>\u{0}"),
+			"&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+		);
+	}
+
+	#[test]
+	fn test_atom() {
+		let atom = feed().atom();
+
+		assert!(atom.contains("<title>Posts &amp; more</title>"));
+		assert!(atom.contains("<updated>2024-01-02T03:04:05+00:00</updated>"));
+		assert!(atom.contains("<id>urn:uuid:00000000-0000-0000-0000-000000000000</id>"));
+		assert!(atom.contains(r#"<content type="html">&lt;p&gt;Hi&lt;/p&gt;</content>"#));
+		assert!(atom.ends_with("</entry></feed>"));
+	}
+
+	#[test]
+	fn test_rss() {
+		let rss = feed().rss();
+
+		assert!(rss.contains("<title>&lt;Hello&gt;</title>"));
+		assert!(rss.contains("<pubDate>Tue, 2 Jan 2024 03:04:05 +0000</pubDate>"));
+		assert!(rss.contains("<category>rust</category>"));
+		assert!(rss.ends_with("</item></channel></rss>"));
+
+		let empty = Feed {
+			updated: DateTime::UNIX_EPOCH,
+			entries: Vec::new(),
+			..feed()
+		};
+
+		assert!(empty
+			.rss()
+			.contains("<lastBuildDate>Thu, 1 Jan 1970 00:00:00 +0000</lastBuildDate>"));
+	}
+}